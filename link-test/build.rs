@@ -0,0 +1,23 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(soxr_linked)");
+    println!("cargo:rerun-if-changed=src/link_test.c");
+
+    // Exported by media-soxr-sys through its `links = "soxr"` key
+    let include = env::var_os("DEP_SOXR_INCLUDE").expect("DEP_SOXR_INCLUDE is not set");
+    let lib = env::var("DEP_SOXR_LIB").expect("DEP_SOXR_LIB is not set");
+    let statik = env::var("DEP_SOXR_STATIC").expect("DEP_SOXR_STATIC is not set");
+
+    println!("cargo:rustc-env=SOXR_LIB={}", lib);
+    println!("cargo:rustc-env=SOXR_STATIC={}", statik);
+
+    // Nothing to link against when libsoxr is loaded at runtime
+    if env::var("DEP_SOXR_SOURCE").as_deref() == Ok("runtime") {
+        return;
+    }
+
+    cc::Build::new().includes(env::split_paths(&include)).file("src/link_test.c").compile("link_test");
+
+    println!("cargo:rustc-cfg=soxr_linked");
+}
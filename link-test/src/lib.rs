@@ -0,0 +1,13 @@
+//! C code compiled and linked against libsoxr using only the metadata
+//! exported by `media-soxr-sys`.
+
+#[cfg(soxr_linked)]
+use std::ffi::c_char;
+
+pub use media_soxr_sys as soxr_sys;
+
+#[cfg(soxr_linked)]
+extern "C" {
+    pub fn link_test_soxr_version() -> *const c_char;
+    pub fn link_test_header_version() -> *const c_char;
+}
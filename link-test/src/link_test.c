@@ -0,0 +1,11 @@
+#include <soxr.h>
+
+const char *link_test_soxr_version(void)
+{
+    return soxr_version();
+}
+
+const char *link_test_header_version(void)
+{
+    return SOXR_THIS_VERSION_STR;
+}
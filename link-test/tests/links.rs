@@ -0,0 +1,25 @@
+use std::path::Path;
+
+#[test]
+fn test_metadata() {
+    assert!(matches!(env!("SOXR_STATIC"), "0" | "1"));
+
+    // Empty when libsoxr is loaded at runtime or found in a default search path
+    for path in std::env::split_paths(env!("SOXR_LIB")).filter(|path| !path.as_os_str().is_empty()) {
+        assert!(Path::new(&path).is_dir(), "{} is not a directory", path.display());
+    }
+}
+
+#[test]
+#[cfg(soxr_linked)]
+fn test_same_library() {
+    use std::ffi::CStr;
+
+    use media_soxr_link_test::*;
+
+    // Both sides must resolve to the same copy of libsoxr
+    assert_eq!(unsafe { link_test_soxr_version() }, unsafe { soxr_sys::soxr_version() });
+
+    let header_version = unsafe { CStr::from_ptr(link_test_header_version()) };
+    assert_eq!(header_version.to_bytes_with_nul(), soxr_sys::SOXR_THIS_VERSION_STR);
+}
@@ -0,0 +1,276 @@
+use std::{
+    ffi::c_void,
+    iter::{StepBy, Take},
+    slice,
+};
+
+use smallvec::{smallvec, SmallVec};
+
+use crate::{
+    data_type::DataType,
+    error::{Error, ErrorContext, ErrorKind, Result},
+    sxor::{input_ptr, output_ptr, validate_channels, validate_layout, Sample, Soxr, DEFAULT_MAX_CHANNELS},
+};
+
+// Output room reserved on top of the frames expected from the input, which
+// also bounds each call while flushing
+const MIN_OUTPUT_FRAMES: usize = 1024;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Layout {
+    Packed,
+    Planar,
+}
+
+/// Owned multichannel audio.
+///
+/// Planar channels share one allocation, each starting at a multiple of a
+/// stride that grows as frames are appended.
+#[derive(Clone, Debug)]
+pub struct AudioBuffer<T> {
+    data: Vec<T>,
+    layout: Layout,
+    channels: u8,
+    frames: usize,
+    stride: usize,
+}
+
+impl<T: Copy + Default> AudioBuffer<T> {
+    /// Creates an empty buffer.
+    pub fn new(layout: Layout, channels: u8) -> Self {
+        Self {
+            data: Vec::new(),
+            layout,
+            channels,
+            frames: 0,
+            stride: 0,
+        }
+    }
+
+    /// Creates a buffer of `frames` silent frames.
+    pub fn with_frames(layout: Layout, channels: u8, frames: usize) -> Self {
+        let mut buffer = Self::new(layout, channels);
+        buffer.reserve_frames(frames);
+        buffer.commit_frames(frames);
+        buffer
+    }
+
+    pub fn from_packed(channels: u8, data: Vec<T>) -> Result<Self> {
+        if channels == 0 {
+            return Err(Error::new(ErrorKind::InvalidChannels(0)));
+        }
+
+        if !data.len().is_multiple_of(channels as usize) {
+            return Err(Error::new(ErrorKind::PartialFrame {
+                len: data.len(),
+                channels: channels as usize,
+            }));
+        }
+
+        Ok(Self {
+            frames: data.len() / channels as usize,
+            data,
+            layout: Layout::Packed,
+            channels,
+            stride: 0,
+        })
+    }
+
+    pub fn from_planar(channels: &[&[T]]) -> Result<Self> {
+        let num_channels = u8::try_from(channels.len()).ok().filter(|&channels| channels > 0).ok_or(ErrorKind::InvalidChannels(channels.len()))?;
+        let frames = channels[0].len();
+
+        if let Some((channel, buf)) = channels.iter().enumerate().find(|(_, buf)| buf.len() != frames) {
+            return Err(Error::new(ErrorKind::ChannelLengthMismatch {
+                channel,
+                expected: frames,
+                actual: buf.len(),
+            }));
+        }
+
+        Ok(Self {
+            data: channels.concat(),
+            layout: Layout::Planar,
+            channels: num_channels,
+            frames,
+            stride: frames,
+        })
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames == 0
+    }
+
+    /// Interleaved samples of a packed buffer.
+    pub fn as_packed(&self) -> Option<&[T]> {
+        (self.layout == Layout::Packed).then_some(self.data.as_slice())
+    }
+
+    pub fn channel(&self, index: usize) -> Option<Samples<'_, T>> {
+        (index < self.channels as usize).then(|| {
+            let (start, step) = self.channel_offsets(index);
+            self.data.get(start..).unwrap_or_default().iter().step_by(step).take(self.frames)
+        })
+    }
+
+    pub fn channel_mut(&mut self, index: usize) -> Option<SamplesMut<'_, T>> {
+        (index < self.channels as usize).then(|| {
+            let (start, step) = self.channel_offsets(index);
+            self.data.get_mut(start..).unwrap_or_default().iter_mut().step_by(step).take(self.frames)
+        })
+    }
+
+    pub fn frame(&self, index: usize) -> Option<Samples<'_, T>> {
+        (index < self.frames).then(|| {
+            let (start, step) = self.frame_offsets(index);
+            self.data[start..].iter().step_by(step).take(self.channels as usize)
+        })
+    }
+
+    pub fn frame_mut(&mut self, index: usize) -> Option<SamplesMut<'_, T>> {
+        (index < self.frames).then(|| {
+            let (start, step) = self.frame_offsets(index);
+            self.data[start..].iter_mut().step_by(step).take(self.channels as usize)
+        })
+    }
+
+    /// Returns a copy of the buffer in `layout`.
+    pub fn to_layout(&self, layout: Layout) -> Self {
+        let mut buffer = Self::with_frames(layout, self.channels, self.frames);
+
+        for channel in 0..self.channels as usize {
+            let (src, src_step) = self.channel_offsets(channel);
+            let (dst, dst_step) = buffer.channel_offsets(channel);
+            for frame in 0..self.frames {
+                buffer.data[dst + frame * dst_step] = self.data[src + frame * src_step];
+            }
+        }
+
+        buffer
+    }
+
+    pub fn clear(&mut self) {
+        self.commit_frames(0);
+    }
+
+    fn channel_offsets(&self, index: usize) -> (usize, usize) {
+        match self.layout {
+            Layout::Packed => (index, self.channels as usize),
+            Layout::Planar => (index * self.stride, 1),
+        }
+    }
+
+    fn frame_offsets(&self, index: usize) -> (usize, usize) {
+        match self.layout {
+            Layout::Packed => (index * self.channels as usize, 1),
+            Layout::Planar => (index, self.stride),
+        }
+    }
+
+    // Makes room for `additional` frames past the current end without changing
+    // the frame count
+    fn reserve_frames(&mut self, additional: usize) {
+        let channels = self.channels as usize;
+        let frames = self.frames + additional;
+
+        match self.layout {
+            Layout::Packed => self.data.resize(frames * channels, T::default()),
+            Layout::Planar if frames > self.stride => {
+                let stride = frames.max(self.stride * 2);
+                let mut data = vec![T::default(); stride * channels];
+                for channel in 0..channels {
+                    let src = channel * self.stride;
+                    data[channel * stride..][..self.frames].copy_from_slice(&self.data[src..src + self.frames]);
+                }
+                self.data = data;
+                self.stride = stride;
+            }
+            Layout::Planar => {}
+        }
+    }
+
+    // Sets the frame count after the reserved room has been written
+    fn commit_frames(&mut self, frames: usize) {
+        self.frames = frames;
+        if self.layout == Layout::Packed {
+            self.data.truncate(frames * self.channels as usize);
+        }
+    }
+
+    // Offset of the first sample of `frame` in each plane
+    fn plane_offsets(&self, frame: usize) -> SmallVec<[usize; DEFAULT_MAX_CHANNELS]> {
+        match self.layout {
+            Layout::Packed => smallvec![frame * self.channels as usize],
+            Layout::Planar => (0..self.channels as usize).map(|channel| channel * self.stride + frame).collect(),
+        }
+    }
+
+    fn ptrs(&self, frame: usize) -> SmallVec<[*const c_void; DEFAULT_MAX_CHANNELS]> {
+        self.plane_offsets(frame).into_iter().map(|offset| self.data[offset..].as_ptr() as *const c_void).collect()
+    }
+
+    fn mut_ptrs(&mut self, frame: usize) -> SmallVec<[*mut c_void; DEFAULT_MAX_CHANNELS]> {
+        self.plane_offsets(frame).into_iter().map(|offset| self.data[offset..].as_mut_ptr() as *mut c_void).collect()
+    }
+}
+
+pub type Samples<'a, T> = Take<StepBy<slice::Iter<'a, T>>>;
+pub type SamplesMut<'a, T> = Take<StepBy<slice::IterMut<'a, T>>>;
+
+fn validate_audio<T: Sample>(context: ErrorContext, data_type: DataType, buffer: &AudioBuffer<T::ValueType>) -> Result<()> {
+    validate_layout::<T>(context, data_type, buffer.layout == Layout::Planar)?;
+    validate_channels(context, buffer.channels as usize)
+}
+
+impl<I: Sample, O: Sample> Soxr<I, O> {
+    /// Processes all of `input`, appending the resampled frames to `output`;
+    /// `None` flushes the remaining output.
+    pub fn process_audio(&mut self, input: Option<&AudioBuffer<I::ValueType>>, output: &mut AudioBuffer<O::ValueType>) -> Result<(usize, usize)> {
+        let context = self.context();
+
+        if let Some(input) = input {
+            validate_audio::<I>(context, self.input_type(), input)?;
+        }
+        validate_audio::<O>(context, self.output_type(), output)?;
+
+        let ratio = context.output_rate / context.input_rate;
+        let ilen = input.map_or(0, |input| input.frames);
+        let (mut itotal, mut ototal) = (0, 0);
+
+        loop {
+            let olen = ((ilen - itotal) as f64 * ratio).ceil() as usize + MIN_OUTPUT_FRAMES;
+            output.reserve_frames(olen);
+
+            let input_ptrs = input.map(|input| input.ptrs(itotal));
+            let mut output_ptrs = output.mut_ptrs(output.frames);
+
+            let input_ptr = input_ptr(input_ptrs.as_deref().unwrap_or_default(), self.input_type().is_planar());
+            let output_ptr = output_ptr(&mut output_ptrs, self.output_type().is_planar());
+
+            let result = unsafe { self.process_raw(input_ptr, ilen - itotal, output_ptr, olen) };
+            output.commit_frames(output.frames + result.as_ref().map_or(0, |&(_, odone)| odone));
+            let (idone, odone) = result?;
+
+            itotal += idone;
+            ototal += odone;
+
+            if itotal == ilen && odone < olen {
+                break;
+            }
+        }
+
+        Ok((itotal, ototal))
+    }
+}
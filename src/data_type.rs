@@ -1,10 +1,8 @@
 use crate::{
-    error::{Error, Result},
+    error::{Error, ErrorKind, Result},
     soxr_sys::soxr_datatype_t,
 };
 
-const UNSUPPORTED_DATA_TYPE: &str = "unsupported data type";
-
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[repr(i32)]
 pub enum DataType {
@@ -27,6 +25,16 @@ impl DataType {
     pub fn is_planar(&self) -> bool {
         matches!(self, DataType::Float32S | DataType::Float64S | DataType::Int32S | DataType::Int16S)
     }
+
+    /// Size of a single sample in bytes, `0` for [`DataType::Dynamic`].
+    pub fn sample_size(&self) -> usize {
+        match self {
+            DataType::Float64I | DataType::Float64S => 8,
+            DataType::Float32I | DataType::Float32S | DataType::Int32I | DataType::Int32S => 4,
+            DataType::Int16I | DataType::Int16S => 2,
+            DataType::Dynamic => 0,
+        }
+    }
 }
 
 impl TryFrom<DataType> for soxr_datatype_t {
@@ -42,7 +50,7 @@ impl TryFrom<DataType> for soxr_datatype_t {
             DataType::Float64S => Ok(soxr_datatype_t::SOXR_FLOAT64_S),
             DataType::Int32S => Ok(soxr_datatype_t::SOXR_INT32_S),
             DataType::Int16S => Ok(soxr_datatype_t::SOXR_INT16_S),
-            DataType::Dynamic => Err(Error::with_str(UNSUPPORTED_DATA_TYPE)),
+            DataType::Dynamic => Err(Error::new(ErrorKind::UnsupportedDataType(dt))),
         }
     }
 }
@@ -0,0 +1,67 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::error::{Error, ErrorKind, Result};
+
+/// Resampling engine selected by libsoxr.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Engine {
+    /// Constant-rate, single precision
+    Cr32,
+    /// Constant-rate, single precision, SSE / NEON
+    Cr32s,
+    /// Constant-rate, double precision
+    Cr64,
+    /// Constant-rate, double precision, AVX
+    Cr64s,
+    /// Variable-rate, single precision
+    Vr32,
+}
+
+impl Engine {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Engine::Cr32 => "cr32",
+            Engine::Cr32s => "cr32s",
+            Engine::Cr64 => "cr64",
+            Engine::Cr64s => "cr64s",
+            Engine::Vr32 => "vr32",
+        }
+    }
+
+    pub fn is_double_precision(&self) -> bool {
+        matches!(self, Engine::Cr64 | Engine::Cr64s)
+    }
+
+    pub fn is_simd(&self) -> bool {
+        matches!(self, Engine::Cr32s | Engine::Cr64s)
+    }
+
+    pub fn is_variable_rate(&self) -> bool {
+        matches!(self, Engine::Vr32)
+    }
+}
+
+impl Display for Engine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Engine {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "cr32" => Ok(Engine::Cr32),
+            "cr32s" => Ok(Engine::Cr32s),
+            "cr64" => Ok(Engine::Cr64),
+            "cr64s" => Ok(Engine::Cr64s),
+            "vr32" => Ok(Engine::Vr32),
+            _ => Err(Error::new(ErrorKind::UnknownEngine(s.to_string()))),
+        }
+    }
+}
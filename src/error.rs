@@ -1,57 +1,159 @@
 use std::{
+    error,
     ffi::CStr,
-    fmt::{self, Debug, Display, Formatter},
-    str,
+    fmt::{self, Display, Formatter},
 };
 
-use crate::soxr_sys;
+use crate::{data_type::DataType, engine::Engine, soxr_sys, spec::QualityFlags, version::Version};
 
 pub(crate) const INVALID_ERROR_STRING: &str = "invalid error string";
-pub(crate) const INVALID_CHANNELS: &str = "invalid number of channels";
+pub(crate) const INVALID_INPUT_LENGTH: &str = "invalid input length";
+pub(crate) const INVALID_PRECISION: &str = "invalid precision";
+pub(crate) const INVALID_PHASE_RESPONSE: &str = "invalid phase response";
+pub(crate) const INVALID_PASSBAND_END: &str = "invalid passband end";
+pub(crate) const INVALID_STOPBAND_BEGIN: &str = "invalid stopband begin";
+pub(crate) const INVALID_DFT_SIZE: &str = "invalid DFT size";
+pub(crate) const INVALID_COEF_SIZE: &str = "invalid coefficient cache size";
+pub(crate) const INVALID_SCALE: &str = "invalid scale";
+pub(crate) const INVALID_VERSION: &str = "invalid version string";
+pub(crate) const INVALID_RATE: &str = "invalid sample rate";
 
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    InvalidChannels(usize),
+    TypeMismatch { expected: DataType, actual: DataType },
+    UnsupportedDataType(DataType),
+    InvalidSpec(&'static str),
+    InvalidArgument(&'static str),
+    BufferSizeMismatch { expected: usize, actual: usize },
+    ChannelLengthMismatch { channel: usize, expected: usize, actual: usize },
+    PartialFrame { len: usize, channels: usize },
+    MisalignedBuffer { align: usize },
+    InvalidByteLength { len: usize, frame_size: usize },
+    LayoutMismatch(DataType),
+    InputFnNotSet,
+    LoadLibrary(String),
+    IncompatibleVersion { expected: Version, actual: Version },
+    UnknownEngine(String),
+    UnexpectedEngine { requested: QualityFlags, actual: Engine },
+    Soxr(String),
+    Other(String),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::InvalidChannels(channels) => write!(f, "invalid number of channels: {}", channels),
+            ErrorKind::TypeMismatch {
+                expected,
+                actual,
+            } => write!(f, "data type mismatch: expected {:?}, got {:?}", expected, actual),
+            ErrorKind::UnsupportedDataType(data_type) => write!(f, "unsupported data type: {:?}", data_type),
+            ErrorKind::InvalidSpec(msg) => write!(f, "{}", msg),
+            ErrorKind::InvalidArgument(msg) => write!(f, "{}", msg),
+            ErrorKind::BufferSizeMismatch {
+                expected,
+                actual,
+            } => write!(f, "buffer size mismatch: expected {}, got {}", expected, actual),
+            ErrorKind::ChannelLengthMismatch {
+                channel,
+                expected,
+                actual,
+            } => write!(f, "channel {} has {} samples, expected {}", channel, actual, expected),
+            ErrorKind::PartialFrame {
+                len,
+                channels,
+            } => write!(f, "buffer length {} ends in a partial frame of {} channels", len, channels),
+            ErrorKind::MisalignedBuffer {
+                align,
+            } => write!(f, "buffer is not aligned to {} bytes", align),
+            ErrorKind::InvalidByteLength {
+                len,
+                frame_size,
+            } => write!(f, "buffer length {} is not a multiple of the {} byte frame size", len, frame_size),
+            ErrorKind::LayoutMismatch(data_type) => write!(f, "buffer layout does not match data type {:?}", data_type),
+            ErrorKind::InputFnNotSet => write!(f, "input function not set"),
+            ErrorKind::LoadLibrary(msg) => write!(f, "failed to load libsoxr: {}", msg),
+            ErrorKind::IncompatibleVersion {
+                expected,
+                actual,
+            } => write!(f, "incompatible libsoxr version: expected {}, got {}", expected, actual),
+            ErrorKind::UnknownEngine(name) => write!(f, "unknown engine: {}", name),
+            ErrorKind::UnexpectedEngine {
+                requested,
+                actual,
+            } => write!(f, "engine {} does not provide the requested {:?}", actual, requested),
+            ErrorKind::Soxr(msg) => write!(f, "{}", msg),
+            ErrorKind::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// Resampler configuration at the time an error occurred.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ErrorContext {
+    pub input_rate: f64,
+    pub output_rate: f64,
+    pub channels: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Error {
-    err: soxr_sys::soxr_error_t,
-    err_str: Option<&'static str>,
+    kind: ErrorKind,
+    context: Option<ErrorContext>,
 }
 
 impl Error {
-    pub fn new(err: soxr_sys::soxr_error_t) -> Self {
+    pub fn new(kind: ErrorKind) -> Self {
         Self {
-            err,
-            err_str: None,
+            kind,
+            context: None,
         }
     }
 
-    pub fn with_str(str: &'static str) -> Self {
-        Self {
-            err: std::ptr::null(),
-            err_str: Some(str),
-        }
+    pub fn other<S: Into<String>>(msg: S) -> Self {
+        Self::new(ErrorKind::Other(msg.into()))
     }
 
-    pub fn as_str(&self) -> &str {
-        if !self.err.is_null() {
-            return unsafe { str::from_utf8(CStr::from_ptr(self.err).to_bytes()).unwrap_or(INVALID_ERROR_STRING) };
-        }
+    pub(crate) fn from_soxr(err: soxr_sys::soxr_error_t) -> Self {
+        let msg = unsafe { CStr::from_ptr(err).to_str().unwrap_or(INVALID_ERROR_STRING) };
 
-        if let Some(str) = self.err_str {
-            return str;
-        }
+        Self::new(ErrorKind::Soxr(msg.to_string()))
+    }
+
+    pub fn with_context(mut self, context: ErrorContext) -> Self {
+        self.context.get_or_insert(context);
+        self
+    }
 
-        INVALID_ERROR_STRING
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn context(&self) -> Option<&ErrorContext> {
+        self.context.as_ref()
     }
 }
 
-impl Display for Error {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.as_str())
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind)
     }
 }
 
-impl Debug for Error {
+impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.debug_tuple("Error").field(&self.as_str()).finish()
+        write!(f, "{}", self.kind)?;
+
+        if let Some(context) = &self.context {
+            write!(f, " (input rate: {}, output rate: {}, channels: {})", context.input_rate, context.output_rate, context.channels)?;
+        }
+
+        Ok(())
     }
 }
 
+impl error::Error for Error {}
+
 pub type Result<T> = std::result::Result<T, Error>;
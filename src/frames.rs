@@ -0,0 +1,159 @@
+use std::{ffi::c_void, ptr};
+
+use crate::{
+    engine::Engine,
+    error::{Error, ErrorKind, Result},
+    spec::{QualitySpec, RuntimeSpec},
+    sxor::{input_ptr, output_ptr, planar_len, Packed, Planar, Sample, Soxr, SoxrBuilder},
+};
+
+/// Buffer layout of a [`Sample`] with a channel count fixed at compile time.
+///
+/// Packed samples are passed as frames, `&[[T; CH]]`, and planar samples as
+/// one slice per channel, `[&[T]; CH]`.
+///
+/// The trait is sealed: [`FixedSoxr`] hands the pointers and lengths it
+/// returns to libsoxr unchecked, so only the layouts defined here implement it.
+pub trait FrameLayout<const CH: usize>: Sample + private::Sealed {
+    type Frames<'a>;
+    type FramesMut<'a>;
+
+    #[doc(hidden)]
+    fn as_ptrs(frames: &Self::Frames<'_>) -> ([*const c_void; CH], [usize; CH]);
+
+    #[doc(hidden)]
+    fn as_mut_ptrs(frames: &mut Self::FramesMut<'_>) -> ([*mut c_void; CH], [usize; CH]);
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl<T> Sealed for super::Packed<T> {}
+    impl<T> Sealed for super::Planar<T> {}
+}
+
+impl<T: 'static, const CH: usize> FrameLayout<CH> for Packed<T>
+where
+    Packed<T>: Sample<ValueType = T>,
+{
+    type Frames<'a> = &'a [[T; CH]];
+    type FramesMut<'a> = &'a mut [[T; CH]];
+
+    fn as_ptrs(frames: &Self::Frames<'_>) -> ([*const c_void; CH], [usize; CH]) {
+        let mut ptrs = [ptr::null(); CH];
+        if let Some(first) = ptrs.first_mut() {
+            *first = frames.as_ptr() as *const c_void;
+        }
+
+        (ptrs, [frames.len(); CH])
+    }
+
+    fn as_mut_ptrs(frames: &mut Self::FramesMut<'_>) -> ([*mut c_void; CH], [usize; CH]) {
+        let mut ptrs = [ptr::null_mut(); CH];
+        if let Some(first) = ptrs.first_mut() {
+            *first = frames.as_mut_ptr() as *mut c_void;
+        }
+
+        (ptrs, [frames.len(); CH])
+    }
+}
+
+impl<T: 'static, const CH: usize> FrameLayout<CH> for Planar<T>
+where
+    Planar<T>: Sample<ValueType = T>,
+{
+    type Frames<'a> = [&'a [T]; CH];
+    type FramesMut<'a> = [&'a mut [T]; CH];
+
+    fn as_ptrs(frames: &Self::Frames<'_>) -> ([*const c_void; CH], [usize; CH]) {
+        (frames.map(|buf| buf.as_ptr() as *const c_void), frames.map(|buf| buf.len()))
+    }
+
+    fn as_mut_ptrs(frames: &mut Self::FramesMut<'_>) -> ([*mut c_void; CH], [usize; CH]) {
+        (frames.each_mut().map(|buf| buf.as_mut_ptr() as *mut c_void), frames.each_ref().map(|buf| buf.len()))
+    }
+}
+
+/// A [`Soxr`] whose channel count is part of its type, so buffers with the
+/// wrong number of channels are rejected at compile time.
+pub struct FixedSoxr<I: FrameLayout<CH>, O: FrameLayout<CH>, const CH: usize> {
+    soxr: Soxr<I, O>,
+}
+
+impl<I: FrameLayout<CH>, O: FrameLayout<CH>, const CH: usize> FixedSoxr<I, O, CH> {
+    pub fn new(input_rate: f64, output_rate: f64, quality_spec: Option<&QualitySpec>, runtime_spec: Option<&RuntimeSpec>) -> Result<Self> {
+        Ok(Self {
+            soxr: Soxr::new(input_rate, output_rate, channels::<CH>()?, quality_spec, runtime_spec)?,
+        })
+    }
+
+    pub fn process(&mut self, input: Option<I::Frames<'_>>, mut output: O::FramesMut<'_>) -> Result<(usize, usize)> {
+        let context = self.soxr.context();
+
+        let (input_ptrs, input_lens) = match &input {
+            Some(input) => I::as_ptrs(input),
+            None => ([ptr::null(); CH], [0; CH]),
+        };
+        let (mut output_ptrs, output_lens) = O::as_mut_ptrs(&mut output);
+
+        let ilen = planar_len(context, input_lens.into_iter())?;
+        let olen = planar_len(context, output_lens.into_iter())?;
+
+        let input_ptrs: &[_] = if input.is_some() {
+            &input_ptrs
+        } else {
+            &[]
+        };
+        let input_ptr = input_ptr(input_ptrs, I::DATA_TYPE.is_planar());
+        let output_ptr = output_ptr(&mut output_ptrs, O::DATA_TYPE.is_planar());
+
+        unsafe { self.soxr.process_raw(input_ptr, ilen, output_ptr, olen) }
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.soxr.error()
+    }
+
+    pub fn num_clips(&self) -> usize {
+        self.soxr.num_clips()
+    }
+
+    pub fn delay(&self) -> f64 {
+        self.soxr.delay()
+    }
+
+    pub fn engine(&self) -> Result<Engine> {
+        self.soxr.engine()
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        self.soxr.clear()
+    }
+
+    pub fn set_io_ratio(&mut self, io_ratio: f64, slew_len: usize) -> Result<()> {
+        self.soxr.set_io_ratio(io_ratio, slew_len)
+    }
+
+    /// Returns the underlying resampler for use with the runtime channel API.
+    pub fn into_inner(self) -> Soxr<I, O> {
+        self.soxr
+    }
+}
+
+impl<I: Sample, O: Sample> SoxrBuilder<I, O> {
+    /// Builds a [`FixedSoxr`] with `CH` channels, overriding
+    /// [`Self::channels`].
+    pub fn build_fixed<const CH: usize>(self) -> Result<FixedSoxr<I, O, CH>>
+    where
+        I: FrameLayout<CH>,
+        O: FrameLayout<CH>,
+    {
+        Ok(FixedSoxr {
+            soxr: self.channels(channels::<CH>()?).build()?,
+        })
+    }
+}
+
+fn channels<const CH: usize>() -> Result<u8> {
+    u8::try_from(CH).map_err(|_| Error::new(ErrorKind::InvalidChannels(CH)))
+}
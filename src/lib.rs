@@ -1,10 +1,29 @@
+pub mod buffer;
 pub mod data_type;
+pub mod engine;
 pub mod error;
+pub mod frames;
+#[cfg(feature = "soxr_lsr")]
+pub mod lsr;
+#[cfg(feature = "soxr_runtime")]
+pub mod runtime;
 pub mod spec;
 pub mod sxor;
+pub mod version;
 
+pub use buffer::*;
 pub use data_type::*;
+pub use engine::*;
 pub use error::*;
+pub use frames::*;
 use media_soxr_sys as soxr_sys;
+#[cfg(feature = "soxr_runtime")]
+use runtime::ensure_loaded;
 pub use spec::*;
 pub use sxor::*;
+pub use version::*;
+
+#[cfg(not(feature = "soxr_runtime"))]
+fn ensure_loaded() -> Result<()> {
+    Ok(())
+}
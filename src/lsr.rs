@@ -0,0 +1,303 @@
+use std::{
+    any::Any,
+    ffi::{c_int, c_long, c_void, CStr},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use crate::{
+    error::{Error, ErrorKind, Result, INVALID_ERROR_STRING, INVALID_INPUT_LENGTH},
+    soxr_sys::lsr as lsr_sys,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(i32)]
+pub enum ConverterType {
+    SincBestQuality   = lsr_sys::SRC_SRCTYPE_e::SRC_SINC_BEST_QUALITY as i32,
+    SincMediumQuality = lsr_sys::SRC_SRCTYPE_e::SRC_SINC_MEDIUM_QUALITY as i32,
+    SincFastest       = lsr_sys::SRC_SRCTYPE_e::SRC_SINC_FASTEST as i32,
+    ZeroOrderHold     = lsr_sys::SRC_SRCTYPE_e::SRC_ZERO_ORDER_HOLD as i32,
+    Linear            = lsr_sys::SRC_SRCTYPE_e::SRC_LINEAR as i32,
+}
+
+impl ConverterType {
+    pub fn name(&self) -> Option<String> {
+        let name = unsafe { lsr_sys::src_get_name(*self as lsr_sys::SRC_SRCTYPE) };
+
+        (!name.is_null()).then(|| unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() })
+    }
+
+    pub fn description(&self) -> Option<String> {
+        let description = unsafe { lsr_sys::src_get_description(*self as lsr_sys::SRC_SRCTYPE) };
+
+        (!description.is_null()).then(|| unsafe { CStr::from_ptr(description).to_string_lossy().into_owned() })
+    }
+}
+
+pub fn version() -> String {
+    unsafe { CStr::from_ptr(lsr_sys::src_get_version()).to_string_lossy().into_owned() }
+}
+
+pub fn is_valid_ratio(ratio: f64) -> bool {
+    unsafe { lsr_sys::src_is_valid_ratio(ratio) != 0 }
+}
+
+fn check(err: lsr_sys::SRC_ERROR) -> Result<()> {
+    if err == 0 {
+        return Ok(());
+    }
+
+    let msg = unsafe { lsr_sys::src_strerror(err) };
+    let msg = if msg.is_null() {
+        INVALID_ERROR_STRING.to_string()
+    } else {
+        unsafe { CStr::from_ptr(msg).to_string_lossy().into_owned() }
+    };
+
+    Err(Error::new(ErrorKind::Soxr(msg)))
+}
+
+fn validate_channels(channels: u8) -> Result<()> {
+    if channels == 0 {
+        return Err(Error::new(ErrorKind::InvalidChannels(0)));
+    }
+
+    Ok(())
+}
+
+// Interleaved buffers have to hold whole frames, otherwise the trailing
+// samples would be silently dropped
+fn frames(len: usize, channels: u8) -> Result<usize> {
+    if !len.is_multiple_of(channels as usize) {
+        return Err(Error::new(ErrorKind::PartialFrame {
+            len,
+            channels: channels as usize,
+        }));
+    }
+
+    Ok(len / channels as usize)
+}
+
+/// Mirrors libsamplerate's `SRC_DATA`: buffers hold interleaved frames, and
+/// `input_frames_used` / `output_frames_gen` are filled in after processing.
+#[derive(Debug)]
+pub struct SrcData<'a> {
+    pub data_in: &'a [f32],
+    pub data_out: &'a mut [f32],
+    pub end_of_input: bool,
+    pub src_ratio: f64,
+    pub input_frames_used: usize,
+    pub output_frames_gen: usize,
+}
+
+impl<'a> SrcData<'a> {
+    pub fn new(data_in: &'a [f32], data_out: &'a mut [f32], src_ratio: f64) -> Self {
+        Self {
+            data_in,
+            data_out,
+            end_of_input: false,
+            src_ratio,
+            input_frames_used: 0,
+            output_frames_gen: 0,
+        }
+    }
+
+    fn as_raw(&mut self, channels: u8) -> Result<lsr_sys::SRC_DATA> {
+        Ok(lsr_sys::SRC_DATA {
+            data_in: self.data_in.as_ptr() as *mut _,
+            data_out: self.data_out.as_mut_ptr(),
+            input_frames: frames(self.data_in.len(), channels)? as c_long,
+            output_frames: frames(self.data_out.len(), channels)? as c_long,
+            input_frames_used: 0,
+            output_frames_gen: 0,
+            end_of_input: self.end_of_input as c_int,
+            src_ratio: self.src_ratio,
+        })
+    }
+
+    fn update(&mut self, raw: &lsr_sys::SRC_DATA) {
+        self.input_frames_used = raw.input_frames_used as usize;
+        self.output_frames_gen = raw.output_frames_gen as usize;
+    }
+}
+
+pub fn simple(data: &mut SrcData, converter_type: ConverterType, channels: u8) -> Result<()> {
+    validate_channels(channels)?;
+
+    let mut raw = data.as_raw(channels)?;
+
+    check(unsafe { lsr_sys::src_simple(&mut raw, converter_type as lsr_sys::SRC_SRCTYPE, channels as c_int) })?;
+
+    data.update(&raw);
+
+    Ok(())
+}
+
+pub struct Converter {
+    state: *mut lsr_sys::SRC_STATE,
+    channels: u8,
+}
+
+impl Converter {
+    pub fn new(converter_type: ConverterType, channels: u8) -> Result<Self> {
+        validate_channels(channels)?;
+
+        let mut err: lsr_sys::SRC_ERROR = 0;
+        let state = unsafe { lsr_sys::src_new(converter_type as lsr_sys::SRC_SRCTYPE, channels as c_int, &mut err) };
+
+        check(err)?;
+
+        Ok(Self {
+            state,
+            channels,
+        })
+    }
+
+    /// Converts as much of `data` as possible; the ratio may change between
+    /// calls, and `end_of_input` flushes the remaining output.
+    pub fn process(&mut self, data: &mut SrcData) -> Result<()> {
+        let mut raw = data.as_raw(self.channels)?;
+
+        check(unsafe { lsr_sys::src_process(self.state, &mut raw) })?;
+
+        data.update(&raw);
+
+        Ok(())
+    }
+
+    /// Sets the ratio immediately instead of smoothly transitioning to the
+    /// ratio given in the next [`SrcData`].
+    pub fn set_ratio(&mut self, src_ratio: f64) -> Result<()> {
+        check(unsafe { lsr_sys::src_set_ratio(self.state, src_ratio) })
+    }
+
+    pub fn reset(&mut self) -> Result<()> {
+        check(unsafe { lsr_sys::src_reset(self.state) })
+    }
+
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+}
+
+// SAFETY: the converter state is only accessed through `&mut self`
+unsafe impl Send for Converter {}
+
+impl Drop for Converter {
+    fn drop(&mut self) {
+        unsafe { lsr_sys::src_delete(self.state) };
+    }
+}
+
+type Callback = Box<dyn FnMut(&mut [f32]) -> usize + Send>;
+
+struct CallbackState {
+    callback: Callback,
+    channels: u8,
+    buf: Vec<f32>,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+unsafe extern "C" fn callback_trampoline(cb_data: *mut c_void, data: *mut *mut f32) -> c_long {
+    let state = unsafe { &mut *(cb_data as *mut CallbackState) };
+
+    match panic::catch_unwind(AssertUnwindSafe(|| (state.callback)(&mut state.buf))) {
+        Ok(frames) => {
+            unsafe { *data = state.buf.as_mut_ptr() };
+            frames.min(state.buf.len() / state.channels as usize) as c_long
+        }
+        Err(payload) => {
+            state.panic = Some(payload);
+            unsafe { *data = ptr::null_mut() };
+            0
+        }
+    }
+}
+
+/// Pull-mode converter mirroring libsamplerate's callback API.
+pub struct CallbackConverter {
+    state: *mut lsr_sys::SRC_STATE,
+    callback: *mut CallbackState,
+    channels: u8,
+}
+
+impl CallbackConverter {
+    /// `callback` fills up to `max_frames` interleaved frames and returns the
+    /// number written; returning `0` signals end-of-input.
+    pub fn new<F>(converter_type: ConverterType, channels: u8, max_frames: usize, callback: F) -> Result<Self>
+    where
+        F: FnMut(&mut [f32]) -> usize + Send + 'static,
+    {
+        validate_channels(channels)?;
+
+        if max_frames == 0 {
+            return Err(Error::new(ErrorKind::InvalidArgument(INVALID_INPUT_LENGTH)));
+        }
+
+        let callback = Box::into_raw(Box::new(CallbackState {
+            callback: Box::new(callback),
+            channels,
+            buf: vec![0.0; max_frames * channels as usize],
+            panic: None,
+        }));
+
+        let mut err: lsr_sys::SRC_ERROR = 0;
+        let state = unsafe {
+            lsr_sys::src_callback_new(
+                Some(callback_trampoline),
+                converter_type as lsr_sys::SRC_SRCTYPE,
+                channels as c_int,
+                &mut err,
+                callback as *mut c_void,
+            )
+        };
+
+        if let Err(err) = check(err) {
+            drop(unsafe { Box::from_raw(callback) });
+            return Err(err);
+        }
+
+        Ok(Self {
+            state,
+            callback,
+            channels,
+        })
+    }
+
+    /// Reads converted interleaved frames into `output`, returning the number
+    /// of frames written.
+    pub fn read(&mut self, src_ratio: f64, output: &mut [f32]) -> Result<usize> {
+        let frames = frames(output.len(), self.channels)?;
+
+        let done = unsafe { lsr_sys::src_callback_read(self.state, src_ratio, frames as c_long, output.as_mut_ptr()) };
+
+        if let Some(payload) = unsafe { (*self.callback).panic.take() } {
+            panic::resume_unwind(payload);
+        }
+
+        if done <= 0 {
+            check(unsafe { lsr_sys::src_error(self.state) })?;
+        }
+
+        Ok(done.max(0) as usize)
+    }
+
+    pub fn reset(&mut self) -> Result<()> {
+        check(unsafe { lsr_sys::src_reset(self.state) })
+    }
+
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+}
+
+// SAFETY: the converter state is only accessed through `&mut self`, and the
+// callback is required to be `Send`
+unsafe impl Send for CallbackConverter {}
+
+impl Drop for CallbackConverter {
+    fn drop(&mut self) {
+        unsafe { lsr_sys::src_delete(self.state) };
+        drop(unsafe { Box::from_raw(self.callback) });
+    }
+}
@@ -0,0 +1,38 @@
+use std::ffi::OsStr;
+
+use crate::{
+    error::{Error, ErrorKind, Result},
+    soxr_sys,
+};
+
+fn load_error(err: soxr_sys::LoadError) -> Error {
+    Error::new(ErrorKind::LoadLibrary(err.to_string()))
+}
+
+pub fn is_loaded() -> bool {
+    soxr_sys::is_loaded()
+}
+
+/// Loads libsoxr from the platform's default library search path.
+///
+/// Creating a resampler does this on demand, so this is only needed to detect
+/// a missing library early.
+pub fn load() -> Result<()> {
+    soxr_sys::load().map_err(load_error)
+}
+
+/// Loads libsoxr from `path`. The first library loaded successfully stays in
+/// use for the lifetime of the process, so this fails if one is already
+/// loaded, including the default one loaded on demand by creating a spec or
+/// resampler.
+pub fn load_from<P: AsRef<OsStr>>(path: P) -> Result<()> {
+    soxr_sys::load_from(path).map_err(load_error)
+}
+
+pub(crate) fn ensure_loaded() -> Result<()> {
+    if is_loaded() {
+        return Ok(());
+    }
+
+    load()
+}
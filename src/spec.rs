@@ -1,8 +1,28 @@
-use std::ffi::{c_uint, c_ulong};
+use std::{
+    ffi::{c_uint, c_ulong},
+    ptr,
+};
 
 use bitflags::bitflags;
 
-use crate::{data_type::DataType, error::Result, soxr_sys};
+use crate::{
+    data_type::DataType,
+    ensure_loaded,
+    error::{
+        Error, ErrorKind, Result, INVALID_COEF_SIZE, INVALID_DFT_SIZE, INVALID_PASSBAND_END, INVALID_PHASE_RESPONSE, INVALID_PRECISION,
+        INVALID_SCALE, INVALID_STOPBAND_BEGIN,
+    },
+    soxr_sys,
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[repr(u32)]
+pub enum Dither {
+    /// Triangular PDF dither, applied when reducing to `Int16`
+    #[default]
+    Tpdf = soxr_sys::SOXR_TPDF,
+    None = soxr_sys::SOXR_NO_DITHER,
+}
 
 #[derive(Clone, Debug)]
 pub struct IOSpec {
@@ -11,10 +31,12 @@ pub struct IOSpec {
 
 impl IOSpec {
     pub fn new(input_type: DataType, output_type: DataType) -> Result<Self> {
+        ensure_loaded()?;
+
         let spec = unsafe { soxr_sys::soxr_io_spec(input_type.try_into()?, output_type.try_into()?) };
 
         if !spec.e.is_null() {
-            return Err(crate::error::Error::new(spec.e as soxr_sys::soxr_error_t));
+            return Err(Error::from_soxr(spec.e as soxr_sys::soxr_error_t));
         }
 
         Ok(Self {
@@ -29,6 +51,71 @@ impl IOSpec {
     pub fn output_type(&self) -> DataType {
         self.io_spec.otype.into()
     }
+
+    pub fn builder(input_type: DataType, output_type: DataType) -> IOSpecBuilder {
+        IOSpecBuilder::new(input_type, output_type)
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.io_spec.scale
+    }
+
+    pub fn dither(&self) -> Dither {
+        if self.io_spec.flags & soxr_sys::SOXR_NO_DITHER as c_ulong != 0 {
+            Dither::None
+        } else {
+            Dither::Tpdf
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IOSpecBuilder {
+    input_type: DataType,
+    output_type: DataType,
+    scale: f64,
+    dither: Dither,
+}
+
+impl IOSpecBuilder {
+    pub fn new(input_type: DataType, output_type: DataType) -> Self {
+        Self {
+            input_type,
+            output_type,
+            scale: 1.0,
+            dither: Dither::default(),
+        }
+    }
+
+    /// Linear gain applied to the output.
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Gain applied to the output, in decibels.
+    pub fn gain_db(mut self, gain_db: f64) -> Self {
+        self.scale = 10f64.powf(gain_db / 20.0);
+        self
+    }
+
+    pub fn dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn build(self) -> Result<IOSpec> {
+        if !self.scale.is_finite() {
+            return Err(Error::new(ErrorKind::InvalidSpec(INVALID_SCALE)));
+        }
+
+        let mut spec = IOSpec::new(self.input_type, self.output_type)?;
+
+        spec.io_spec.scale = self.scale;
+        spec.io_spec.flags = (spec.io_spec.flags & !(soxr_sys::SOXR_NO_DITHER as c_ulong)) | self.dither as c_ulong;
+
+        Ok(spec)
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -37,8 +124,26 @@ pub enum QualityRecipe {
     Quick    = soxr_sys::SOXR_QQ,
     Low      = soxr_sys::SOXR_LQ,
     Medium   = soxr_sys::SOXR_MQ,
+    Bits16   = soxr_sys::SOXR_16_BITQ,
     High     = soxr_sys::SOXR_20_BITQ,
+    Bits24   = soxr_sys::SOXR_24_BITQ,
     VeryHigh = soxr_sys::SOXR_28_BITQ,
+    Bits32   = soxr_sys::SOXR_32_BITQ,
+    /// Emulates libsamplerate's `SRC_SINC_BEST_QUALITY`
+    Lsr0     = soxr_sys::SOXR_LSR0Q,
+    /// Emulates libsamplerate's `SRC_SINC_MEDIUM_QUALITY`
+    Lsr1     = soxr_sys::SOXR_LSR1Q,
+    /// Emulates libsamplerate's `SRC_SINC_FASTEST`
+    Lsr2     = soxr_sys::SOXR_LSR2Q,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[repr(u32)]
+pub enum PhaseResponse {
+    #[default]
+    Linear       = soxr_sys::SOXR_LINEAR_PHASE,
+    Intermediate = soxr_sys::SOXR_INTERMEDIATE_PHASE,
+    Minimum      = soxr_sys::SOXR_MINIMUM_PHASE,
 }
 
 bitflags! {
@@ -53,32 +158,321 @@ bitflags! {
     }
 }
 
+#[derive(Clone, Debug)]
 pub struct QualitySpec {
     pub(crate) quality_spec: soxr_sys::soxr_quality_spec,
 }
 
 impl QualitySpec {
     pub fn new(recipe: QualityRecipe, flags: QualityFlags) -> Result<Self> {
-        let spec = unsafe { soxr_sys::soxr_quality_spec(recipe as c_ulong, flags.bits() as c_ulong) };
+        Self::with_recipe_bits(recipe as c_ulong, flags)
+    }
+
+    fn with_recipe_bits(recipe: c_ulong, flags: QualityFlags) -> Result<Self> {
+        ensure_loaded()?;
+
+        let spec = unsafe { soxr_sys::soxr_quality_spec(recipe, flags.bits() as c_ulong) };
 
         if !spec.e.is_null() {
-            return Err(crate::error::Error::new(spec.e as soxr_sys::soxr_error_t));
+            return Err(Error::from_soxr(spec.e as soxr_sys::soxr_error_t));
         }
 
         Ok(Self {
             quality_spec: spec,
         })
     }
+
+    pub fn builder(recipe: QualityRecipe) -> QualitySpecBuilder {
+        QualitySpecBuilder::new(recipe)
+    }
+
+    pub fn precision(&self) -> f64 {
+        self.quality_spec.precision
+    }
+
+    pub fn phase_response(&self) -> f64 {
+        self.quality_spec.phase_response
+    }
+
+    pub fn passband_end(&self) -> f64 {
+        self.quality_spec.passband_end
+    }
+
+    pub fn stopband_begin(&self) -> f64 {
+        self.quality_spec.stopband_begin
+    }
+
+    pub fn flags(&self) -> QualityFlags {
+        QualityFlags::from_bits_truncate(self.quality_spec.flags as u32)
+    }
+}
+
+/// Builds a [`QualitySpec`] from a recipe, overriding individual filter
+/// parameters.
+#[derive(Clone, Debug)]
+pub struct QualitySpecBuilder {
+    recipe: QualityRecipe,
+    phase: PhaseResponse,
+    steep_filter: bool,
+    flags: QualityFlags,
+    precision: Option<f64>,
+    phase_response: Option<f64>,
+    passband_end: Option<f64>,
+    stopband_begin: Option<f64>,
 }
 
+impl QualitySpecBuilder {
+    pub fn new(recipe: QualityRecipe) -> Self {
+        Self {
+            recipe,
+            phase: PhaseResponse::default(),
+            steep_filter: false,
+            flags: QualityFlags::empty(),
+            precision: None,
+            phase_response: None,
+            passband_end: None,
+            stopband_begin: None,
+        }
+    }
+
+    pub fn flags(mut self, flags: QualityFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn phase(mut self, phase: PhaseResponse) -> Self {
+        self.phase = phase;
+        self
+    }
+
+    /// Selects a steeper filter, trading a longer impulse response for a
+    /// narrower transition band.
+    pub fn steep_filter(mut self, steep_filter: bool) -> Self {
+        self.steep_filter = steep_filter;
+        self
+    }
+
+    /// Conversion precision in bits, in the range `0..=33`.
+    pub fn precision(mut self, precision: f64) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Phase response from `0` (minimum) through `50` (linear) to `100`
+    /// (maximum); overrides the preset selected with [`Self::phase`].
+    pub fn phase_response(mut self, phase_response: f64) -> Self {
+        self.phase_response = Some(phase_response);
+        self
+    }
+
+    /// End of the passband relative to the Nyquist frequency, in the range
+    /// `0..1`.
+    pub fn passband_end(mut self, passband_end: f64) -> Self {
+        self.passband_end = Some(passband_end);
+        self
+    }
+
+    /// Start of the stopband relative to the Nyquist frequency; must be
+    /// greater than the passband end.
+    pub fn stopband_begin(mut self, stopband_begin: f64) -> Self {
+        self.stopband_begin = Some(stopband_begin);
+        self
+    }
+
+    pub fn build(self) -> Result<QualitySpec> {
+        let mut recipe = self.recipe as c_ulong | self.phase as c_ulong;
+        if self.steep_filter {
+            recipe |= soxr_sys::SOXR_STEEP_FILTER as c_ulong;
+        }
+
+        let mut spec = QualitySpec::with_recipe_bits(recipe, self.flags)?;
+        let quality_spec = &mut spec.quality_spec;
+
+        if let Some(precision) = self.precision {
+            if !(0.0..=33.0).contains(&precision) {
+                return Err(Error::new(ErrorKind::InvalidSpec(INVALID_PRECISION)));
+            }
+            quality_spec.precision = precision;
+        }
+
+        if let Some(phase_response) = self.phase_response {
+            if !(0.0..=100.0).contains(&phase_response) {
+                return Err(Error::new(ErrorKind::InvalidSpec(INVALID_PHASE_RESPONSE)));
+            }
+            quality_spec.phase_response = phase_response;
+        }
+
+        if let Some(passband_end) = self.passband_end {
+            if !(passband_end > 0.0 && passband_end < 1.0) {
+                return Err(Error::new(ErrorKind::InvalidSpec(INVALID_PASSBAND_END)));
+            }
+            quality_spec.passband_end = passband_end;
+        }
+
+        if let Some(stopband_begin) = self.stopband_begin {
+            if !stopband_begin.is_finite() {
+                return Err(Error::new(ErrorKind::InvalidSpec(INVALID_STOPBAND_BEGIN)));
+            }
+            quality_spec.stopband_begin = stopband_begin;
+        }
+
+        if (self.passband_end.is_some() || self.stopband_begin.is_some()) && quality_spec.stopband_begin <= quality_spec.passband_end {
+            return Err(Error::new(ErrorKind::InvalidSpec(INVALID_STOPBAND_BEGIN)));
+        }
+
+        Ok(spec)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[repr(u32)]
+pub enum CoefInterpolation {
+    #[default]
+    Auto = soxr_sys::SOXR_COEF_INTERP_AUTO,
+    Low  = soxr_sys::SOXR_COEF_INTERP_LOW,
+    High = soxr_sys::SOXR_COEF_INTERP_HIGH,
+}
+
+#[derive(Clone, Debug)]
 pub struct RuntimeSpec {
     pub(crate) runtime_spec: soxr_sys::soxr_runtime_spec,
 }
 
 impl RuntimeSpec {
+    /// `num_threads` only takes effect with the `soxr_openmp` feature.
     pub fn new(num_threads: u32) -> Self {
+        // The defaults of soxr_runtime_spec, built here so that no library has
+        // to be loaded
+        Self {
+            runtime_spec: soxr_sys::soxr_runtime_spec {
+                log2_min_dft_size: 10,
+                log2_large_dft_size: 17,
+                coef_size_kbytes: 400,
+                num_threads: num_threads as c_uint,
+                e: ptr::null_mut(),
+                flags: 0,
+            },
+        }
+    }
+
+    pub fn builder() -> RuntimeSpecBuilder {
+        RuntimeSpecBuilder::new()
+    }
+
+    pub fn log2_min_dft_size(&self) -> u32 {
+        self.runtime_spec.log2_min_dft_size
+    }
+
+    pub fn log2_large_dft_size(&self) -> u32 {
+        self.runtime_spec.log2_large_dft_size
+    }
+
+    pub fn coef_size_kbytes(&self) -> u32 {
+        self.runtime_spec.coef_size_kbytes
+    }
+
+    pub fn num_threads(&self) -> u32 {
+        self.runtime_spec.num_threads
+    }
+
+    pub fn coef_interpolation(&self) -> CoefInterpolation {
+        match self.runtime_spec.flags as u32 & soxr_sys::SOXR_COEF_INTERP_HIGH {
+            soxr_sys::SOXR_COEF_INTERP_LOW => CoefInterpolation::Low,
+            soxr_sys::SOXR_COEF_INTERP_HIGH => CoefInterpolation::High,
+            _ => CoefInterpolation::Auto,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RuntimeSpecBuilder {
+    num_threads: u32,
+    log2_min_dft_size: Option<u32>,
+    log2_large_dft_size: Option<u32>,
+    coef_size_kbytes: Option<u32>,
+    coef_interpolation: CoefInterpolation,
+}
+
+impl Default for RuntimeSpecBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuntimeSpecBuilder {
+    pub fn new() -> Self {
         Self {
-            runtime_spec: unsafe { soxr_sys::soxr_runtime_spec(num_threads as c_uint) },
+            num_threads: 1,
+            log2_min_dft_size: None,
+            log2_large_dft_size: None,
+            coef_size_kbytes: None,
+            coef_interpolation: CoefInterpolation::default(),
+        }
+    }
+
+    /// Number of worker threads, where `0` lets libsoxr decide; only effective
+    /// with the `soxr_openmp` feature.
+    pub fn num_threads(mut self, num_threads: u32) -> Self {
+        self.num_threads = num_threads;
+        self
+    }
+
+    /// Smallest DFT size as a power of two, in the range `8..=15`.
+    pub fn log2_min_dft_size(mut self, log2_min_dft_size: u32) -> Self {
+        self.log2_min_dft_size = Some(log2_min_dft_size);
+        self
+    }
+
+    /// DFT size as a power of two above which SNR is traded for speed, in the
+    /// range `8..=20`.
+    pub fn log2_large_dft_size(mut self, log2_large_dft_size: u32) -> Self {
+        self.log2_large_dft_size = Some(log2_large_dft_size);
+        self
+    }
+
+    /// Size of the filter coefficient cache in kilobytes.
+    pub fn coef_size_kbytes(mut self, coef_size_kbytes: u32) -> Self {
+        self.coef_size_kbytes = Some(coef_size_kbytes);
+        self
+    }
+
+    /// Coefficient interpolation used in variable-rate mode.
+    pub fn coef_interpolation(mut self, coef_interpolation: CoefInterpolation) -> Self {
+        self.coef_interpolation = coef_interpolation;
+        self
+    }
+
+    pub fn build(self) -> Result<RuntimeSpec> {
+        let mut spec = RuntimeSpec::new(self.num_threads);
+        let runtime_spec = &mut spec.runtime_spec;
+
+        if let Some(log2_min_dft_size) = self.log2_min_dft_size {
+            if !(8..=15).contains(&log2_min_dft_size) {
+                return Err(Error::new(ErrorKind::InvalidSpec(INVALID_DFT_SIZE)));
+            }
+            runtime_spec.log2_min_dft_size = log2_min_dft_size;
         }
+
+        if let Some(log2_large_dft_size) = self.log2_large_dft_size {
+            if !(8..=20).contains(&log2_large_dft_size) {
+                return Err(Error::new(ErrorKind::InvalidSpec(INVALID_DFT_SIZE)));
+            }
+            runtime_spec.log2_large_dft_size = log2_large_dft_size;
+        }
+
+        if runtime_spec.log2_min_dft_size > runtime_spec.log2_large_dft_size {
+            return Err(Error::new(ErrorKind::InvalidSpec(INVALID_DFT_SIZE)));
+        }
+
+        if let Some(coef_size_kbytes) = self.coef_size_kbytes {
+            if coef_size_kbytes == 0 {
+                return Err(Error::new(ErrorKind::InvalidSpec(INVALID_COEF_SIZE)));
+            }
+            runtime_spec.coef_size_kbytes = coef_size_kbytes;
+        }
+
+        runtime_spec.flags = (runtime_spec.flags & !(soxr_sys::SOXR_COEF_INTERP_HIGH as c_ulong)) | self.coef_interpolation as c_ulong;
+
+        Ok(spec)
     }
 }
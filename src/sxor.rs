@@ -1,20 +1,25 @@
 use std::{
-    ffi::{c_uint, c_void, CStr},
+    any::Any,
+    ffi::{c_uint, c_ulong, c_void, CStr},
     marker::PhantomData,
-    ptr,
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
 };
 
-use smallvec::SmallVec;
+use smallvec::{smallvec, SmallVec};
 
 use crate::{
     data_type::DataType,
-    error::{Error, Result, INVALID_CHANNELS},
+    engine::Engine,
+    ensure_loaded,
+    error::{Error, ErrorContext, ErrorKind, Result, INVALID_INPUT_LENGTH, INVALID_RATE},
     soxr_sys,
-    spec::{IOSpec, QualitySpec, RuntimeSpec},
+    spec::{Dither, IOSpec, QualityFlags, QualityRecipe, QualitySpec, RuntimeSpec},
+    version::check_runtime_version,
 };
 
 pub trait Sample {
-    type ValueType: Copy + 'static;
+    type ValueType: Copy + Default + 'static;
     const DATA_TYPE: DataType;
 }
 
@@ -77,19 +82,497 @@ pub enum SampleBufferMut<'a, T: Sample> {
     Planar(&'a mut [&'a mut [T::ValueType]]),
 }
 
-const DEFAULT_MAX_CHANNELS: usize = 16;
+/// Typed buffer whose data type is only known at runtime, for use with
+/// [`Soxr::process_buffer`].
+///
+/// [`DynamicBuffer::Bytes`] holds raw samples of the tagged data type, aligned
+/// to the sample size: a single interleaved plane for packed types, or one
+/// plane per channel for planar types.
+pub enum DynamicBuffer<'a> {
+    PackedF32(&'a [f32]),
+    PackedF64(&'a [f64]),
+    PackedI32(&'a [i32]),
+    PackedI16(&'a [i16]),
+    PlanarF32(&'a [&'a [f32]]),
+    PlanarF64(&'a [&'a [f64]]),
+    PlanarI32(&'a [&'a [i32]]),
+    PlanarI16(&'a [&'a [i16]]),
+    Bytes(DataType, &'a [&'a [u8]]),
+}
+
+pub enum DynamicBufferMut<'a> {
+    PackedF32(&'a mut [f32]),
+    PackedF64(&'a mut [f64]),
+    PackedI32(&'a mut [i32]),
+    PackedI16(&'a mut [i16]),
+    PlanarF32(&'a mut [&'a mut [f32]]),
+    PlanarF64(&'a mut [&'a mut [f64]]),
+    PlanarI32(&'a mut [&'a mut [i32]]),
+    PlanarI16(&'a mut [&'a mut [i16]]),
+    Bytes(DataType, &'a mut [&'a mut [u8]]),
+}
+
+impl DynamicBuffer<'_> {
+    pub fn data_type(&self) -> DataType {
+        match self {
+            DynamicBuffer::PackedF32(_) => DataType::Float32I,
+            DynamicBuffer::PackedF64(_) => DataType::Float64I,
+            DynamicBuffer::PackedI32(_) => DataType::Int32I,
+            DynamicBuffer::PackedI16(_) => DataType::Int16I,
+            DynamicBuffer::PlanarF32(_) => DataType::Float32S,
+            DynamicBuffer::PlanarF64(_) => DataType::Float64S,
+            DynamicBuffer::PlanarI32(_) => DataType::Int32S,
+            DynamicBuffer::PlanarI16(_) => DataType::Int16S,
+            DynamicBuffer::Bytes(data_type, _) => *data_type,
+        }
+    }
+}
+
+impl DynamicBufferMut<'_> {
+    pub fn data_type(&self) -> DataType {
+        match self {
+            DynamicBufferMut::PackedF32(_) => DataType::Float32I,
+            DynamicBufferMut::PackedF64(_) => DataType::Float64I,
+            DynamicBufferMut::PackedI32(_) => DataType::Int32I,
+            DynamicBufferMut::PackedI16(_) => DataType::Int16I,
+            DynamicBufferMut::PlanarF32(_) => DataType::Float32S,
+            DynamicBufferMut::PlanarF64(_) => DataType::Float64S,
+            DynamicBufferMut::PlanarI32(_) => DataType::Int32S,
+            DynamicBufferMut::PlanarI16(_) => DataType::Int16S,
+            DynamicBufferMut::Bytes(data_type, _) => *data_type,
+        }
+    }
+}
+
+pub(crate) const DEFAULT_MAX_CHANNELS: usize = 16;
+
+pub(crate) fn validate_channels(context: ErrorContext, channels: usize) -> Result<()> {
+    if context.channels as usize != channels || channels == 0 {
+        Err(Error::new(ErrorKind::InvalidChannels(channels)).with_context(context))
+    } else {
+        Ok(())
+    }
+}
+
+// The buffer variant has to match the layout libsoxr was configured with,
+// otherwise it would read the channel pointers as samples or vice versa
+pub(crate) fn validate_layout<T: Sample>(context: ErrorContext, data_type: DataType, planar: bool) -> Result<()> {
+    if T::DATA_TYPE == DataType::Dynamic {
+        return Err(Error::new(ErrorKind::UnsupportedDataType(T::DATA_TYPE)).with_context(context));
+    }
+
+    if data_type.is_planar() != planar {
+        return Err(Error::new(ErrorKind::LayoutMismatch(data_type)).with_context(context));
+    }
+
+    Ok(())
+}
+
+// Byte buffers are handed to libsoxr as samples of the configured type, so
+// they have to be aligned to and sized in whole frames of that type. Empty
+// ones are never read and may have a dangling, unaligned pointer
+fn bytes_len(context: ErrorContext, data_type: DataType, ptr: *const u8, len: usize, channels: usize) -> Result<usize> {
+    let sample_size = data_type.sample_size();
+    if len > 0 && !(ptr as usize).is_multiple_of(sample_size) {
+        return Err(Error::new(ErrorKind::MisalignedBuffer {
+            align: sample_size,
+        })
+        .with_context(context));
+    }
+
+    let frame_size = sample_size * channels;
+    if !len.is_multiple_of(frame_size) {
+        return Err(Error::new(ErrorKind::InvalidByteLength {
+            len,
+            frame_size,
+        })
+        .with_context(context));
+    }
+
+    Ok(len / sample_size)
+}
+
+fn packed_len(context: ErrorContext, len: usize) -> Result<usize> {
+    let channels = context.channels as usize;
+    if channels == 0 {
+        return Err(Error::new(ErrorKind::InvalidChannels(0)).with_context(context));
+    }
+
+    if !len.is_multiple_of(channels) {
+        return Err(Error::new(ErrorKind::PartialFrame {
+            len,
+            channels,
+        })
+        .with_context(context));
+    }
+
+    Ok(len / channels)
+}
+
+pub(crate) fn planar_len(context: ErrorContext, lens: impl ExactSizeIterator<Item = usize>) -> Result<usize> {
+    validate_channels(context, lens.len())?;
+
+    let mut lens = lens.enumerate();
+    let (_, expected) = lens.next().unwrap_or_default();
+
+    for (channel, actual) in lens {
+        if actual != expected {
+            return Err(Error::new(ErrorKind::ChannelLengthMismatch {
+                channel,
+                expected,
+                actual,
+            })
+            .with_context(context));
+        }
+    }
+
+    Ok(expected)
+}
+
+struct InputBuffer {
+    bufs: SmallVec<[*const c_void; DEFAULT_MAX_CHANNELS]>,
+    planar: bool,
+    len: usize,
+}
+
+impl InputBuffer {
+    fn new<T: Sample>(context: ErrorContext, data_type: DataType, input: Option<SampleBuffer<T>>) -> Result<Self> {
+        match input {
+            Some(SampleBuffer::Packed(buf)) => {
+                validate_layout::<T>(context, data_type, false)?;
+                Ok(Self {
+                    bufs: smallvec![buf.as_ptr() as *const c_void],
+                    planar: false,
+                    len: packed_len(context, buf.len())?,
+                })
+            }
+            Some(SampleBuffer::Planar(bufs)) => {
+                validate_layout::<T>(context, data_type, true)?;
+                Ok(Self {
+                    len: planar_len(context, bufs.iter().map(|buf| buf.len()))?,
+                    bufs: bufs.iter().map(|buf| buf.as_ptr() as *const c_void).collect(),
+                    planar: true,
+                })
+            }
+            None => Ok(Self {
+                bufs: SmallVec::new(),
+                planar: false,
+                len: 0,
+            }),
+        }
+    }
+
+    fn from_dynamic(context: ErrorContext, data_type: DataType, input: Option<DynamicBuffer>) -> Result<Self> {
+        let Some(input) = input else {
+            return Ok(Self {
+                bufs: SmallVec::new(),
+                planar: false,
+                len: 0,
+            });
+        };
+
+        if input.data_type() != data_type {
+            return Err(Error::new(ErrorKind::TypeMismatch {
+                expected: data_type,
+                actual: input.data_type(),
+            })
+            .with_context(context));
+        }
+
+        match input {
+            DynamicBuffer::PackedF32(buf) => Self::new::<Packed<f32>>(context, data_type, Some(SampleBuffer::Packed(buf))),
+            DynamicBuffer::PackedF64(buf) => Self::new::<Packed<f64>>(context, data_type, Some(SampleBuffer::Packed(buf))),
+            DynamicBuffer::PackedI32(buf) => Self::new::<Packed<i32>>(context, data_type, Some(SampleBuffer::Packed(buf))),
+            DynamicBuffer::PackedI16(buf) => Self::new::<Packed<i16>>(context, data_type, Some(SampleBuffer::Packed(buf))),
+            DynamicBuffer::PlanarF32(bufs) => Self::new::<Planar<f32>>(context, data_type, Some(SampleBuffer::Planar(bufs))),
+            DynamicBuffer::PlanarF64(bufs) => Self::new::<Planar<f64>>(context, data_type, Some(SampleBuffer::Planar(bufs))),
+            DynamicBuffer::PlanarI32(bufs) => Self::new::<Planar<i32>>(context, data_type, Some(SampleBuffer::Planar(bufs))),
+            DynamicBuffer::PlanarI16(bufs) => Self::new::<Planar<i16>>(context, data_type, Some(SampleBuffer::Planar(bufs))),
+            DynamicBuffer::Bytes(_, bufs) if data_type.is_planar() => {
+                let lens = bufs
+                    .iter()
+                    .map(|buf| bytes_len(context, data_type, buf.as_ptr(), buf.len(), 1))
+                    .collect::<Result<SmallVec<[usize; DEFAULT_MAX_CHANNELS]>>>()?;
+                Ok(Self {
+                    len: planar_len(context, lens.into_iter())?,
+                    bufs: bufs.iter().map(|buf| buf.as_ptr() as *const c_void).collect(),
+                    planar: true,
+                })
+            }
+            DynamicBuffer::Bytes(_, bufs) => {
+                let [buf] = bufs else {
+                    return Err(Error::new(ErrorKind::LayoutMismatch(data_type)).with_context(context));
+                };
+                let len = bytes_len(context, data_type, buf.as_ptr(), buf.len(), context.channels as usize)?;
+                Ok(Self {
+                    bufs: smallvec![buf.as_ptr() as *const c_void],
+                    planar: false,
+                    len: packed_len(context, len)?,
+                })
+            }
+        }
+    }
+
+    fn as_ptr(&self) -> soxr_sys::soxr_in_t {
+        input_ptr(&self.bufs, self.planar)
+    }
+}
+
+struct OutputBuffer {
+    bufs: SmallVec<[*mut c_void; DEFAULT_MAX_CHANNELS]>,
+    planar: bool,
+    len: usize,
+}
+
+impl OutputBuffer {
+    fn new<T: Sample>(context: ErrorContext, data_type: DataType, output: SampleBufferMut<T>) -> Result<Self> {
+        match output {
+            SampleBufferMut::Packed(buf) => {
+                validate_layout::<T>(context, data_type, false)?;
+                Ok(Self {
+                    len: packed_len(context, buf.len())?,
+                    bufs: smallvec![buf.as_mut_ptr() as *mut c_void],
+                    planar: false,
+                })
+            }
+            SampleBufferMut::Planar(bufs) => {
+                validate_layout::<T>(context, data_type, true)?;
+                Ok(Self {
+                    len: planar_len(context, bufs.iter().map(|buf| buf.len()))?,
+                    bufs: bufs.iter_mut().map(|buf| buf.as_mut_ptr() as *mut c_void).collect(),
+                    planar: true,
+                })
+            }
+        }
+    }
+
+    fn from_dynamic(context: ErrorContext, data_type: DataType, output: DynamicBufferMut) -> Result<Self> {
+        if output.data_type() != data_type {
+            return Err(Error::new(ErrorKind::TypeMismatch {
+                expected: data_type,
+                actual: output.data_type(),
+            })
+            .with_context(context));
+        }
+
+        match output {
+            DynamicBufferMut::PackedF32(buf) => Self::new::<Packed<f32>>(context, data_type, SampleBufferMut::Packed(buf)),
+            DynamicBufferMut::PackedF64(buf) => Self::new::<Packed<f64>>(context, data_type, SampleBufferMut::Packed(buf)),
+            DynamicBufferMut::PackedI32(buf) => Self::new::<Packed<i32>>(context, data_type, SampleBufferMut::Packed(buf)),
+            DynamicBufferMut::PackedI16(buf) => Self::new::<Packed<i16>>(context, data_type, SampleBufferMut::Packed(buf)),
+            DynamicBufferMut::PlanarF32(bufs) => Self::new::<Planar<f32>>(context, data_type, SampleBufferMut::Planar(bufs)),
+            DynamicBufferMut::PlanarF64(bufs) => Self::new::<Planar<f64>>(context, data_type, SampleBufferMut::Planar(bufs)),
+            DynamicBufferMut::PlanarI32(bufs) => Self::new::<Planar<i32>>(context, data_type, SampleBufferMut::Planar(bufs)),
+            DynamicBufferMut::PlanarI16(bufs) => Self::new::<Planar<i16>>(context, data_type, SampleBufferMut::Planar(bufs)),
+            DynamicBufferMut::Bytes(_, bufs) if data_type.is_planar() => {
+                let lens = bufs
+                    .iter()
+                    .map(|buf| bytes_len(context, data_type, buf.as_ptr(), buf.len(), 1))
+                    .collect::<Result<SmallVec<[usize; DEFAULT_MAX_CHANNELS]>>>()?;
+                Ok(Self {
+                    len: planar_len(context, lens.into_iter())?,
+                    bufs: bufs.iter_mut().map(|buf| buf.as_mut_ptr() as *mut c_void).collect(),
+                    planar: true,
+                })
+            }
+            DynamicBufferMut::Bytes(_, bufs) => {
+                let [buf] = bufs else {
+                    return Err(Error::new(ErrorKind::LayoutMismatch(data_type)).with_context(context));
+                };
+                let len = bytes_len(context, data_type, buf.as_ptr(), buf.len(), context.channels as usize)?;
+                Ok(Self {
+                    len: packed_len(context, len)?,
+                    bufs: smallvec![buf.as_mut_ptr() as *mut c_void],
+                    planar: false,
+                })
+            }
+        }
+    }
+
+    fn as_mut_ptr(&mut self) -> soxr_sys::soxr_out_t {
+        output_ptr(&mut self.bufs, self.planar)
+    }
+}
 
+/// Input argument of `soxr_process` for the given channel pointers; without
+/// any it is null, which flushes the resampler unlike an empty buffer.
+pub(crate) fn input_ptr(ptrs: &[*const c_void], planar: bool) -> soxr_sys::soxr_in_t {
+    if ptrs.is_empty() {
+        ptr::null()
+    } else if planar {
+        ptrs.as_ptr() as soxr_sys::soxr_in_t
+    } else {
+        ptrs[0]
+    }
+}
+
+pub(crate) fn output_ptr(ptrs: &mut [*mut c_void], planar: bool) -> soxr_sys::soxr_out_t {
+    if planar {
+        ptrs.as_mut_ptr() as soxr_sys::soxr_out_t
+    } else {
+        ptrs[0]
+    }
+}
+
+/// Supplies input to a resampler in pull mode.
+///
+/// `read` fills at most the given buffer and returns the number of frames
+/// written; returning `0` signals end-of-input.
+pub trait Source<T: Sample> {
+    fn read(&mut self, buffer: SampleBufferMut<T>) -> Result<usize>;
+}
+
+impl<T: Sample, F> Source<T> for F
+where
+    F: FnMut(SampleBufferMut<T>) -> Result<usize>,
+{
+    fn read(&mut self, buffer: SampleBufferMut<T>) -> Result<usize> {
+        self(buffer)
+    }
+}
+
+struct InputFnState<T: Sample> {
+    source: Box<dyn Source<T> + Send>,
+    channels: u8,
+    max_len: usize,
+    planar: bool,
+    bufs: Vec<Vec<T::ValueType>>,
+    // Planes handed to the source, kept to avoid allocating in the callback
+    planes: Vec<*mut [T::ValueType]>,
+    ptrs: Vec<*const c_void>,
+    error: Option<Error>,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl<T: Sample> InputFnState<T> {
+    fn new(source: Box<dyn Source<T> + Send>, channels: u8, max_len: usize) -> Self {
+        let mut state = Self {
+            source,
+            channels,
+            max_len,
+            planar: T::DATA_TYPE.is_planar(),
+            bufs: Vec::new(),
+            planes: Vec::new(),
+            ptrs: Vec::new(),
+            error: None,
+            panic: None,
+        };
+        state.set_channels(channels);
+        state
+    }
+
+    fn set_channels(&mut self, channels: u8) {
+        self.channels = channels;
+        self.bufs = if self.planar {
+            (0..channels).map(|_| vec![T::ValueType::default(); self.max_len]).collect()
+        } else {
+            vec![vec![T::ValueType::default(); self.max_len * channels as usize]]
+        };
+        self.planes = Vec::with_capacity(self.bufs.len());
+        self.ptrs = Vec::with_capacity(self.bufs.len());
+    }
+
+    fn read(&mut self, requested_len: usize) -> Result<usize> {
+        let len = requested_len.min(self.max_len);
+
+        let done = if self.planar {
+            self.planes.clear();
+            self.planes.extend(self.bufs.iter_mut().map(|buf| &mut buf[..len] as *mut [T::ValueType]));
+
+            // SAFETY: raw slice pointers have the layout of slice references, and
+            // each plane points into a distinct buffer that is not touched while
+            // the source runs
+            let bufs = unsafe { slice::from_raw_parts_mut(self.planes.as_mut_ptr() as *mut &mut [T::ValueType], self.planes.len()) };
+            self.source.read(SampleBufferMut::Planar(bufs))?
+        } else {
+            self.source.read(SampleBufferMut::Packed(&mut self.bufs[0][..len * self.channels as usize]))?
+        };
+
+        if done > len {
+            return Err(Error::new(ErrorKind::BufferSizeMismatch {
+                expected: len,
+                actual: done,
+            }));
+        }
+
+        Ok(done)
+    }
+
+    fn has_failed(&self) -> bool {
+        self.error.is_some() || self.panic.is_some()
+    }
+
+    // Resumes a panic or returns an error raised by the source
+    fn take_failure(&mut self) -> Result<()> {
+        if let Some(payload) = self.panic.take() {
+            panic::resume_unwind(payload);
+        }
+
+        self.error.take().map_or(Ok(()), Err)
+    }
+
+    fn as_ptr(&mut self) -> soxr_sys::soxr_in_t {
+        self.ptrs.clear();
+        self.ptrs.extend(self.bufs.iter().map(|buf| buf.as_ptr() as *const c_void));
+
+        if self.planar {
+            self.ptrs.as_ptr() as soxr_sys::soxr_in_t
+        } else {
+            self.ptrs[0]
+        }
+    }
+}
+
+unsafe extern "C" fn input_fn_trampoline<T: Sample>(state: *mut c_void, data: *mut soxr_sys::soxr_in_t, requested_len: usize) -> usize {
+    let state = unsafe { &mut *(state as *mut InputFnState<T>) };
+
+    // A null data pointer with a zero length reports failure to libsoxr, whereas a
+    // non-null one marks end-of-input
+    match panic::catch_unwind(AssertUnwindSafe(|| state.read(requested_len))) {
+        Ok(Ok(len)) => {
+            unsafe { *data = state.as_ptr() };
+            len
+        }
+        Ok(Err(err)) => {
+            state.error = Some(err);
+            unsafe { *data = ptr::null() };
+            0
+        }
+        Err(payload) => {
+            state.panic = Some(payload);
+            unsafe { *data = ptr::null() };
+            0
+        }
+    }
+}
+
+/// A streaming resampler.
+///
+/// `Soxr` is [`Send`], so it can be created on one thread and moved to another,
+/// and it is [`Sync`]: the `&self` methods such as [`Soxr::delay`] and
+/// [`Soxr::engine`] only read libsoxr state, while everything that mutates it
+/// takes `&mut self`.
 pub struct Soxr<I: Sample = DynamicSample, O: Sample = DynamicSample> {
     soxr: soxr_sys::soxr_t,
+    input_rate: f64,
+    output_rate: f64,
     channels: u8,
-    input_data_type: Option<DataType>,
-    output_data_type: Option<DataType>,
+    input_fn: Option<*mut InputFnState<I>>,
+    input_type: DataType,
+    output_type: DataType,
     _phantom: PhantomData<(I, O)>,
 }
 
 impl<I: Sample, O: Sample> Soxr<I, O> {
-    pub fn version() -> String {
-        unsafe { CStr::from_ptr(soxr_sys::soxr_version()).to_string_lossy().into_owned() }
+    /// With the `soxr_runtime` feature, fails if libsoxr cannot be loaded.
+    pub fn version() -> Result<String> {
+        ensure_loaded()?;
+
+        Ok(unsafe { CStr::from_ptr(soxr_sys::soxr_version()).to_string_lossy().into_owned() })
+    }
+
+    pub fn builder(input_rate: f64, output_rate: f64) -> SoxrBuilder<I, O> {
+        SoxrBuilder::new(input_rate, output_rate)
     }
 
     pub fn new(
@@ -99,14 +582,53 @@ impl<I: Sample, O: Sample> Soxr<I, O> {
         quality_spec: Option<&QualitySpec>,
         runtime_spec: Option<&RuntimeSpec>,
     ) -> Result<Self> {
+        Self::builder(input_rate, output_rate).channels(num_channels).specs(quality_spec, runtime_spec).build()
+    }
+
+    pub fn new_with_data_type(
+        input_data_type: DataType,
+        output_data_type: DataType,
+        input_rate: f64,
+        output_rate: f64,
+        num_channels: u8,
+        quality_spec: Option<&QualitySpec>,
+        runtime_spec: Option<&RuntimeSpec>,
+    ) -> Result<Self> {
+        Self::builder(input_rate, output_rate)
+            .channels(num_channels)
+            .data_types(input_data_type, output_data_type)
+            .specs(quality_spec, runtime_spec)
+            .build()
+    }
+
+    pub fn new_with_io_spec(
+        input_rate: f64,
+        output_rate: f64,
+        num_channels: u8,
+        io_spec: &IOSpec,
+        quality_spec: Option<&QualitySpec>,
+        runtime_spec: Option<&RuntimeSpec>,
+    ) -> Result<Self> {
+        Self::builder(input_rate, output_rate).channels(num_channels).io_spec(io_spec).specs(quality_spec, runtime_spec).build()
+    }
+
+    fn create(
+        context: ErrorContext,
+        input_type: DataType,
+        output_type: DataType,
+        io_spec: &IOSpec,
+        quality_spec: Option<&QualitySpec>,
+        runtime_spec: Option<&RuntimeSpec>,
+    ) -> Result<Self> {
+        ensure_loaded().and_then(|_| check_runtime_version()).map_err(|err| err.with_context(context))?;
+
         let mut err: soxr_sys::soxr_error_t = ptr::null_mut();
-        let io_spec = IOSpec::new(I::DATA_TYPE, O::DATA_TYPE)?;
 
         let soxr = unsafe {
             soxr_sys::soxr_create(
-                input_rate,
-                output_rate,
-                num_channels as c_uint,
+                context.input_rate,
+                context.output_rate,
+                context.channels as c_uint,
                 &mut err,
                 &io_spec.io_spec,
                 quality_spec.map_or(ptr::null(), |spec| &spec.quality_spec),
@@ -115,36 +637,60 @@ impl<I: Sample, O: Sample> Soxr<I, O> {
         };
 
         if !err.is_null() {
-            return Err(Error::new(err));
+            return Err(Error::from_soxr(err).with_context(context));
         }
 
-        Ok(Self {
+        let soxr = Self {
             soxr,
-            channels: num_channels,
-            input_data_type: None,
-            output_data_type: None,
+            input_rate: context.input_rate,
+            output_rate: context.output_rate,
+            channels: context.channels,
+            input_fn: None,
+            input_type,
+            output_type,
             _phantom: PhantomData,
-        })
+        };
+
+        if let Some(quality_spec) = quality_spec {
+            soxr.check_engine(quality_spec.flags())?;
+        }
+
+        Ok(soxr)
     }
 
-    pub fn new_with_data_type(
-        input_data_type: DataType,
-        output_data_type: DataType,
+    pub fn oneshot(
         input_rate: f64,
         output_rate: f64,
         num_channels: u8,
+        input: SampleBuffer<I>,
+        output: SampleBufferMut<O>,
         quality_spec: Option<&QualitySpec>,
         runtime_spec: Option<&RuntimeSpec>,
-    ) -> Result<Self> {
-        let mut err: soxr_sys::soxr_error_t = ptr::null_mut();
-        let io_spec = IOSpec::new(input_data_type, output_data_type)?;
+    ) -> Result<(usize, usize)> {
+        let context = ErrorContext {
+            input_rate,
+            output_rate,
+            channels: num_channels,
+        };
+        let io_spec = IOSpec::new(I::DATA_TYPE, O::DATA_TYPE).map_err(|err| err.with_context(context))?;
+        check_runtime_version().map_err(|err| err.with_context(context))?;
+        let input = InputBuffer::new(context, I::DATA_TYPE, Some(input))?;
+        let mut output = OutputBuffer::new(context, O::DATA_TYPE, output)?;
 
-        let soxr = unsafe {
-            soxr_sys::soxr_create(
+        let mut idone: usize = 0;
+        let mut odone: usize = 0;
+
+        let err = unsafe {
+            soxr_sys::soxr_oneshot(
                 input_rate,
                 output_rate,
                 num_channels as c_uint,
-                &mut err,
+                input.as_ptr(),
+                input.len,
+                &mut idone,
+                output.as_mut_ptr(),
+                output.len,
+                &mut odone,
                 &io_spec.io_spec,
                 quality_spec.map_or(ptr::null(), |spec| &spec.quality_spec),
                 runtime_spec.map_or(ptr::null(), |spec| &spec.runtime_spec),
@@ -152,63 +698,96 @@ impl<I: Sample, O: Sample> Soxr<I, O> {
         };
 
         if !err.is_null() {
-            return Err(Error::new(err));
+            return Err(Error::from_soxr(err).with_context(context));
         }
 
-        Ok(Self {
-            soxr,
-            channels: num_channels,
-            input_data_type: Some(input_data_type),
-            output_data_type: Some(output_data_type),
-            _phantom: PhantomData,
-        })
+        Ok((idone, odone))
     }
 
-    fn validate_channels(&self, channels: usize) -> Result<()> {
-        if self.channels as usize != channels || channels == 0 {
-            Err(Error::with_str(INVALID_CHANNELS))
-        } else {
-            Ok(())
+    // Data types libsoxr was configured with
+    pub(crate) fn input_type(&self) -> DataType {
+        self.input_type
+    }
+
+    pub(crate) fn output_type(&self) -> DataType {
+        self.output_type
+    }
+
+    pub(crate) fn context(&self) -> ErrorContext {
+        ErrorContext {
+            input_rate: self.input_rate,
+            output_rate: self.output_rate,
+            channels: self.channels,
         }
     }
 
-    unsafe fn process_internal(&mut self, in_ptr: *const c_void, in_len: usize, out_ptr: *mut c_void, out_len: usize) -> Result<(usize, usize)> {
-        let mut idone: usize = 0;
-        let mut odone: usize = 0;
+    // libsoxr falls back to another engine when the requested one is not
+    // available, e.g. variable-rate together with double precision
+    fn check_engine(&self, flags: QualityFlags) -> Result<()> {
+        let requested = flags & (QualityFlags::DoublePrecision | QualityFlags::VR);
+        if requested.is_empty() {
+            return Ok(());
+        }
+
+        // Engines added by other libsoxr builds cannot be verified, so only a
+        // known engine lacking the requested capability is an error
+        let engine = match self.engine() {
+            Ok(engine) => engine,
+            Err(err) if matches!(err.kind(), ErrorKind::UnknownEngine(_)) => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        if (requested.contains(QualityFlags::VR) && !engine.is_variable_rate()) ||
+            (requested.contains(QualityFlags::DoublePrecision) && !engine.is_double_precision())
+        {
+            return Err(Error::new(ErrorKind::UnexpectedEngine {
+                requested,
+                actual: engine,
+            })
+            .with_context(self.context()));
+        }
 
-        let err = unsafe { soxr_sys::soxr_process(self.soxr, in_ptr, in_len, &mut idone, out_ptr, out_len, &mut odone) };
+        Ok(())
+    }
 
+    fn check(&self, err: soxr_sys::soxr_error_t) -> Result<()> {
         if !err.is_null() {
-            return Err(Error::new(err));
+            return Err(Error::from_soxr(err).with_context(self.context()));
         }
 
-        Ok((idone, odone))
+        Ok(())
     }
 
-    pub fn process(&mut self, input: Option<SampleBuffer<I>>, output: SampleBufferMut<O>) -> Result<(usize, usize)> {
-        let (in_ptr, in_len, _in_vec) = match input {
-            Some(SampleBuffer::Packed(buf)) => (buf.as_ptr() as *const _, buf.len() / self.channels as usize, None),
-            Some(SampleBuffer::Planar(bufs)) => {
-                self.validate_channels(bufs.len())?;
-                let samples = bufs[0].len();
-                let buf_vec: SmallVec<[_; DEFAULT_MAX_CHANNELS]> = bufs.iter().map(|buf| buf.as_ref().as_ptr()).collect();
-                (buf_vec.as_ptr() as *const _, samples, Some(buf_vec))
-            }
-            None => (ptr::null(), 0, None),
-        };
+    fn process_internal(&mut self, input: InputBuffer, mut output: OutputBuffer) -> Result<(usize, usize)> {
+        unsafe { self.process_raw(input.as_ptr(), input.len, output.as_mut_ptr(), output.len) }
+    }
 
-        let (out_ptr, out_len, _out_vec) = match output {
-            SampleBufferMut::Packed(buf) => (buf.as_mut_ptr() as *mut _, buf.len() / self.channels as usize, None),
-            SampleBufferMut::Planar(bufs) => {
-                self.validate_channels(bufs.len())?;
-                let samples = bufs[0].len();
-                let mut buf_vec: SmallVec<[_; DEFAULT_MAX_CHANNELS]> = bufs.iter_mut().map(|buf| buf.as_mut().as_mut_ptr()).collect();
+    /// # Safety
+    ///
+    /// The pointers have to describe buffers of the configured data types
+    /// holding at least `ilen` and `olen` frames.
+    pub(crate) unsafe fn process_raw(
+        &mut self,
+        input: soxr_sys::soxr_in_t,
+        ilen: usize,
+        output: soxr_sys::soxr_out_t,
+        olen: usize,
+    ) -> Result<(usize, usize)> {
+        let mut idone: usize = 0;
+        let mut odone: usize = 0;
 
-                (buf_vec.as_mut_ptr() as *mut _, samples, Some(buf_vec))
-            }
-        };
+        let err = unsafe { soxr_sys::soxr_process(self.soxr, input, ilen, &mut idone, output, olen, &mut odone) };
+
+        self.check(err)?;
+
+        Ok((idone, odone))
+    }
 
-        unsafe { self.process_internal(in_ptr, in_len, out_ptr, out_len) }
+    pub fn process(&mut self, input: Option<SampleBuffer<I>>, output: SampleBufferMut<O>) -> Result<(usize, usize)> {
+        let input = InputBuffer::new(self.context(), self.input_type(), input)?;
+        let output = OutputBuffer::new(self.context(), self.output_type(), output)?;
+
+        self.process_internal(input, output)
     }
 
     pub fn process_dynamic<In: Sample, Out: Sample>(
@@ -216,48 +795,99 @@ impl<I: Sample, O: Sample> Soxr<I, O> {
         input: Option<SampleBuffer<In>>,
         output: SampleBufferMut<Out>,
     ) -> Result<(usize, usize)> {
-        if let Some(input_data_type) = self.input_data_type {
-            if input.is_some() && input_data_type != In::DATA_TYPE {
-                return Err(Error::with_str("input data type mismatch"));
-            }
+        if input.is_some() && self.input_type() != In::DATA_TYPE {
+            return Err(Error::new(ErrorKind::TypeMismatch {
+                expected: self.input_type(),
+                actual: In::DATA_TYPE,
+            })
+            .with_context(self.context()));
         }
 
-        if let Some(output_data_type) = self.output_data_type {
-            if output_data_type != Out::DATA_TYPE {
-                return Err(Error::with_str("output data type mismatch"));
-            }
+        if self.output_type() != Out::DATA_TYPE {
+            return Err(Error::new(ErrorKind::TypeMismatch {
+                expected: self.output_type(),
+                actual: Out::DATA_TYPE,
+            })
+            .with_context(self.context()));
         }
 
-        let (in_ptr, in_len, _in_vec) = match input {
-            Some(SampleBuffer::Packed(buf)) => (buf.as_ptr() as *const _, buf.len() / self.channels as usize, None),
-            Some(SampleBuffer::Planar(bufs)) => {
-                self.validate_channels(bufs.len())?;
-                let samples = bufs[0].len();
-                let buf_vec: SmallVec<[_; DEFAULT_MAX_CHANNELS]> = bufs.iter().map(|buf| buf.as_ref().as_ptr()).collect();
-                (buf_vec.as_ptr() as *const _, samples, Some(buf_vec))
-            }
-            None => (ptr::null(), 0, None),
+        let input = InputBuffer::new(self.context(), self.input_type(), input)?;
+        let output = OutputBuffer::new(self.context(), self.output_type(), output)?;
+
+        self.process_internal(input, output)
+    }
+
+    /// Switches the resampler to pull mode, where [`Soxr::output`] requests
+    /// input from `source` in chunks of at most `max_len` frames.
+    pub fn set_input_fn<S: Source<I> + Send + 'static>(&mut self, source: S, max_len: usize) -> Result<()> {
+        if I::DATA_TYPE == DataType::Dynamic {
+            return Err(Error::new(ErrorKind::UnsupportedDataType(I::DATA_TYPE)).with_context(self.context()));
+        }
+
+        if max_len == 0 {
+            return Err(Error::new(ErrorKind::InvalidArgument(INVALID_INPUT_LENGTH)).with_context(self.context()));
+        }
+
+        let state = Box::into_raw(Box::new(InputFnState::new(Box::new(source), self.channels, max_len)));
+
+        if let Err(err) = self.register_input_fn(state) {
+            drop(unsafe { Box::from_raw(state) });
+            return Err(err);
+        }
+
+        if let Some(old_state) = self.input_fn.replace(state) {
+            drop(unsafe { Box::from_raw(old_state) });
+        }
+
+        Ok(())
+    }
+
+    fn register_input_fn(&mut self, state: *mut InputFnState<I>) -> Result<()> {
+        let max_len = unsafe { (*state).max_len };
+        let err = unsafe { soxr_sys::soxr_set_input_fn(self.soxr, Some(input_fn_trampoline::<I>), state as *mut c_void, max_len) };
+
+        self.check(err)
+    }
+
+    /// Fills `output` with frames resampled from the input function's source.
+    ///
+    /// If the source fails after frames were written to `output`, they are
+    /// returned first and the failure is reported by the next call.
+    pub fn output(&mut self, output: SampleBufferMut<O>) -> Result<usize> {
+        let Some(state) = self.input_fn else {
+            return Err(Error::new(ErrorKind::InputFnNotSet).with_context(self.context()));
         };
 
-        let (out_ptr, out_len, _out_vec) = match output {
-            SampleBufferMut::Packed(buf) => (buf.as_mut_ptr() as *mut _, buf.len() / self.channels as usize, None),
-            SampleBufferMut::Planar(bufs) => {
-                self.validate_channels(bufs.len())?;
-                let samples = bufs[0].len();
-                let mut buf_vec: SmallVec<[_; DEFAULT_MAX_CHANNELS]> = bufs.iter_mut().map(|buf| buf.as_mut().as_mut_ptr()).collect();
+        let state = unsafe { &mut *state };
 
-                (buf_vec.as_mut_ptr() as *mut _, samples, Some(buf_vec))
+        state.take_failure()?;
+
+        let mut output = OutputBuffer::new(self.context(), self.output_type(), output)?;
+
+        let odone = unsafe { soxr_sys::soxr_output(self.soxr, output.as_mut_ptr(), output.len) };
+
+        if state.has_failed() {
+            unsafe { soxr_sys::soxr_set_error(self.soxr, ptr::null()) };
+
+            if odone > 0 {
+                return Ok(odone);
             }
-        };
 
-        unsafe { self.process_internal(in_ptr, in_len, out_ptr, out_len) }
+            state.take_failure()?;
+        }
+
+        let err = unsafe { soxr_sys::soxr_error(self.soxr) };
+
+        self.check(err)?;
+
+        Ok(odone)
     }
 
     pub fn error(&self) -> Option<String> {
         let err = unsafe { soxr_sys::soxr_error(self.soxr) };
 
         if !err.is_null() {
-            return Some(Error::new(err).to_string());
+            return Some(Error::from_soxr(err).to_string());
         }
 
         None
@@ -277,43 +907,254 @@ impl<I: Sample, O: Sample> Soxr<I, O> {
         unsafe { soxr_sys::soxr_delay(self.soxr) }
     }
 
-    pub fn engine(&self) -> String {
-        unsafe { CStr::from_ptr(soxr_sys::soxr_engine(self.soxr)).to_string_lossy().into_owned() }
+    pub fn engine(&self) -> Result<Engine> {
+        let name = unsafe { CStr::from_ptr(soxr_sys::soxr_engine(self.soxr)) };
+
+        name.to_string_lossy().parse().map_err(|err: Error| err.with_context(self.context()))
     }
 
+    /// Resets the resampler to its initial state, discarding any buffered
+    /// input and output. In pull mode the source is kept.
     pub fn clear(&mut self) -> Result<()> {
         let err = unsafe { soxr_sys::soxr_clear(self.soxr) };
+        self.check(err)?;
 
-        if !err.is_null() {
-            return Err(Error::new(err));
+        // soxr_clear keeps the input function but resets its maximum length
+        match self.input_fn {
+            Some(state) => self.register_input_fn(state),
+            None => Ok(()),
         }
-
-        Ok(())
     }
 
     pub fn set_io_ratio(&mut self, io_ratio: f64, slew_len: usize) -> Result<()> {
         let err = unsafe { soxr_sys::soxr_set_io_ratio(self.soxr, io_ratio, slew_len) };
 
-        if !err.is_null() {
-            return Err(Error::new(err));
+        self.check(err)
+    }
+
+    /// Changes the number of channels, e.g. to follow a stream layout change.
+    ///
+    /// libsoxr cannot change the channel count of a resampler that has started
+    /// processing, so it is cleared first and any input it still buffers is
+    /// discarded; flush it with `process(None, ..)` beforehand to keep it. In
+    /// pull mode the source is kept and is read with the new channel count.
+    pub fn set_num_channels(&mut self, num_channels: u8) -> Result<()> {
+        if num_channels == 0 {
+            return Err(Error::new(ErrorKind::InvalidChannels(0)).with_context(self.context()));
+        }
+
+        if num_channels == self.channels {
+            return Ok(());
+        }
+
+        self.clear()?;
+
+        let err = unsafe { soxr_sys::soxr_set_num_channels(self.soxr, num_channels as c_uint) };
+        self.check(err)?;
+
+        self.channels = num_channels;
+
+        if let Some(state) = self.input_fn {
+            unsafe { (*state).set_channels(num_channels) };
         }
 
         Ok(())
     }
+}
 
-    pub fn set_num_channels(&mut self, num_channels: u32) -> Result<()> {
-        let err = unsafe { soxr_sys::soxr_set_num_channels(self.soxr, num_channels) };
+/// Builds a [`Soxr`], validating the configuration before libsoxr is called.
+///
+/// Typed samples fix the data types; with [`DynamicSample`] they have to be
+/// set with [`Self::data_types`] or [`Self::io_spec`].
+pub struct SoxrBuilder<I: Sample = DynamicSample, O: Sample = DynamicSample> {
+    input_rate: f64,
+    output_rate: f64,
+    channels: u8,
+    input_type: Option<DataType>,
+    output_type: Option<DataType>,
+    scale: f64,
+    dither: Dither,
+    recipe: Option<QualityRecipe>,
+    flags: QualityFlags,
+    variable_rate: bool,
+    quality_spec: Option<QualitySpec>,
+    runtime_spec: Option<RuntimeSpec>,
+    _phantom: PhantomData<(I, O)>,
+}
 
-        if !err.is_null() {
-            return Err(Error::new(err));
+impl<I: Sample, O: Sample> SoxrBuilder<I, O> {
+    /// Starts from a mono resampler with libsoxr's default quality.
+    pub fn new(input_rate: f64, output_rate: f64) -> Self {
+        Self {
+            input_rate,
+            output_rate,
+            channels: 1,
+            input_type: None,
+            output_type: None,
+            scale: 1.0,
+            dither: Dither::default(),
+            recipe: None,
+            flags: QualityFlags::empty(),
+            variable_rate: false,
+            quality_spec: None,
+            runtime_spec: None,
+            _phantom: PhantomData,
         }
+    }
 
-        Ok(())
+    pub fn channels(mut self, channels: u8) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// Must match the sample types unless they are [`DynamicSample`].
+    pub fn data_types(mut self, input_type: DataType, output_type: DataType) -> Self {
+        self.input_type = Some(input_type);
+        self.output_type = Some(output_type);
+        self
+    }
+
+    /// Takes the data types, scale and dither from `io_spec`.
+    pub fn io_spec(mut self, io_spec: &IOSpec) -> Self {
+        self.input_type = Some(io_spec.input_type());
+        self.output_type = Some(io_spec.output_type());
+        self.scale = io_spec.scale();
+        self.dither = io_spec.dither();
+        self
+    }
+
+    /// Linear gain applied to the output.
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Gain applied to the output, in decibels.
+    pub fn gain_db(mut self, gain_db: f64) -> Self {
+        self.scale = 10f64.powf(gain_db / 20.0);
+        self
+    }
+
+    pub fn dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn quality(mut self, recipe: QualityRecipe) -> Self {
+        self.recipe = Some(recipe);
+        self
+    }
+
+    pub fn quality_flags(mut self, flags: QualityFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Used as is in place of [`Self::quality`] and [`Self::quality_flags`];
+    /// only [`Self::variable_rate`] is added to its flags.
+    pub fn quality_spec(mut self, quality_spec: QualitySpec) -> Self {
+        self.quality_spec = Some(quality_spec);
+        self
+    }
+
+    /// Allows changing the rate ratio with [`Soxr::set_io_ratio`].
+    pub fn variable_rate(mut self, variable_rate: bool) -> Self {
+        self.variable_rate = variable_rate;
+        self
+    }
+
+    pub fn runtime_spec(mut self, runtime_spec: RuntimeSpec) -> Self {
+        self.runtime_spec = Some(runtime_spec);
+        self
+    }
+
+    fn specs(mut self, quality_spec: Option<&QualitySpec>, runtime_spec: Option<&RuntimeSpec>) -> Self {
+        self.quality_spec = quality_spec.cloned();
+        self.runtime_spec = runtime_spec.cloned();
+        self
+    }
+
+    pub fn build(self) -> Result<Soxr<I, O>> {
+        let context = ErrorContext {
+            input_rate: self.input_rate,
+            output_rate: self.output_rate,
+            channels: self.channels,
+        };
+
+        if !(self.input_rate > 0.0 && self.input_rate.is_finite() && self.output_rate > 0.0 && self.output_rate.is_finite()) {
+            return Err(Error::new(ErrorKind::InvalidArgument(INVALID_RATE)).with_context(context));
+        }
+
+        if self.channels == 0 {
+            return Err(Error::new(ErrorKind::InvalidChannels(0)).with_context(context));
+        }
+
+        let input_type = resolve_data_type(I::DATA_TYPE, self.input_type).map_err(|err| err.with_context(context))?;
+        let output_type = resolve_data_type(O::DATA_TYPE, self.output_type).map_err(|err| err.with_context(context))?;
+
+        let io_spec =
+            IOSpec::builder(input_type, output_type).scale(self.scale).dither(self.dither).build().map_err(|err| err.with_context(context))?;
+
+        let mut flags = self.flags;
+        if self.variable_rate {
+            flags |= QualityFlags::VR;
+        }
+
+        let quality_spec = match self.quality_spec {
+            Some(mut spec) => {
+                if self.variable_rate {
+                    spec.quality_spec.flags |= QualityFlags::VR.bits() as c_ulong;
+                }
+                Some(spec)
+            }
+            None if self.recipe.is_some() || !flags.is_empty() => {
+                Some(QualitySpec::new(self.recipe.unwrap_or(QualityRecipe::High), flags).map_err(|err| err.with_context(context))?)
+            }
+            None => None,
+        };
+
+        Soxr::create(context, input_type, output_type, &io_spec, quality_spec.as_ref(), self.runtime_spec.as_ref())
+    }
+}
+
+fn resolve_data_type(expected: DataType, configured: Option<DataType>) -> Result<DataType> {
+    match configured {
+        None if expected == DataType::Dynamic => Err(Error::new(ErrorKind::UnsupportedDataType(expected))),
+        None => Ok(expected),
+        Some(actual) if expected == DataType::Dynamic || actual == expected => Ok(actual),
+        Some(actual) => Err(Error::new(ErrorKind::TypeMismatch {
+            expected,
+            actual,
+        })),
+    }
+}
+
+/// Runtime-typed resampler, created through [`SoxrBuilder::data_types`] once
+/// the formats are known.
+impl Soxr<DynamicSample, DynamicSample> {
+    /// Like [`Soxr::process`], with buffers that must match the configured
+    /// data types.
+    pub fn process_buffer(&mut self, input: Option<DynamicBuffer>, output: DynamicBufferMut) -> Result<(usize, usize)> {
+        let input = InputBuffer::from_dynamic(self.context(), self.input_type(), input)?;
+        let output = OutputBuffer::from_dynamic(self.context(), self.output_type(), output)?;
+
+        self.process_internal(input, output)
     }
 }
 
+// SAFETY: libsoxr keeps no thread-local or global mutable state per instance,
+// and the pull-mode source is required to be `Send`
+unsafe impl<I: Sample, O: Sample> Send for Soxr<I, O> {}
+
+// SAFETY: the methods taking `&self` only read from the libsoxr instance
+unsafe impl<I: Sample, O: Sample> Sync for Soxr<I, O> {}
+
 impl<I: Sample, O: Sample> Drop for Soxr<I, O> {
     fn drop(&mut self) {
         unsafe { soxr_sys::soxr_delete(self.soxr) }
+
+        if let Some(state) = self.input_fn.take() {
+            drop(unsafe { Box::from_raw(state) });
+        }
     }
 }
@@ -0,0 +1,120 @@
+use std::{
+    ffi::CStr,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    sync::OnceLock,
+};
+
+use crate::{
+    ensure_loaded,
+    error::{Error, ErrorKind, Result, INVALID_VERSION},
+    soxr_sys,
+};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+/// Version of the libsoxr headers the bindings were generated from.
+pub const HEADER_VERSION: Version = Version::parse_header(soxr_sys::SOXR_THIS_VERSION_STR);
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    const fn parse_header(version: &[u8]) -> Self {
+        let mut parts = [0u32; 3];
+        let mut part = 0;
+        let mut i = 0;
+
+        while i < version.len() && version[i] != 0 {
+            match version[i] {
+                b'.' => part += 1,
+                digit @ b'0'..=b'9' => parts[part] = parts[part] * 10 + (digit - b'0') as u32,
+                _ => panic!("invalid SOXR_THIS_VERSION_STR"),
+            }
+            i += 1;
+        }
+
+        Self::new(parts[0], parts[1], parts[2])
+    }
+
+    /// Version of the libsoxr in use, which may differ from [`HEADER_VERSION`]
+    /// when linked dynamically.
+    pub fn runtime() -> Result<Self> {
+        ensure_loaded()?;
+
+        let version = unsafe { CStr::from_ptr(soxr_sys::soxr_version()) };
+        let version = version.to_str().map_err(|_| Error::new(ErrorKind::InvalidArgument(INVALID_VERSION)))?;
+
+        Self::parse_runtime(version)
+    }
+
+    /// Parses a version as reported by libsoxr, e.g. `libsoxr-0.1.3`, ignoring
+    /// any prefix and anything after the patch number such as a distribution
+    /// suffix.
+    pub fn parse_runtime(version: &str) -> Result<Self> {
+        let version = version.trim_start_matches(|c: char| !c.is_ascii_digit());
+        let end = version.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(version.len());
+
+        version[..end].parse()
+    }
+
+    /// libsoxr keeps its ABI within a minor release.
+    pub fn is_compatible_with(&self, other: &Version) -> bool {
+        self.major == other.major && self.minor == other.minor
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split('.').map(|part| part.parse::<u32>());
+
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => Ok(Self::new(major, minor, patch)),
+            _ => Err(Error::new(ErrorKind::InvalidArgument(INVALID_VERSION))),
+        }
+    }
+}
+
+// Checked once, before the first resampler is created
+pub(crate) fn check_runtime_version() -> Result<()> {
+    static RESULT: OnceLock<Result<()>> = OnceLock::new();
+
+    // A failure to load is not cached, as loading may be retried
+    ensure_loaded()?;
+
+    RESULT
+        .get_or_init(|| {
+            // A version in an unknown format is not checked
+            let Ok(runtime) = Version::runtime() else {
+                return Ok(());
+            };
+
+            if !runtime.is_compatible_with(&HEADER_VERSION) {
+                return Err(Error::new(ErrorKind::IncompatibleVersion {
+                    expected: HEADER_VERSION,
+                    actual: runtime,
+                }));
+            }
+
+            Ok(())
+        })
+        .clone()
+}
@@ -1,37 +1,317 @@
-use std::{env, path::PathBuf};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
 
+#[cfg(feature = "bindgen")]
 use bindgen::EnumVariation::Rust;
+#[cfg(not(feature = "cc"))]
 use cmake::Config;
 
-fn main() {
-    println!("cargo:rerun-if-changed=soxr");
-    println!("cargo:rerun-if-changed=include/wrapper.h");
+const GENERATED_BINDINGS: &str = "generated/soxr.rs";
 
-    let dynamic = if env::var("CARGO_FEATURE_DYNAMIC").is_ok() {
-        println!("cargo:rustc-link-lib=dylib=soxr");
-        true
-    } else {
-        println!("cargo:rustc-link-lib=static=soxr");
-        false
-    };
+fn link_libs(kind: &str, lsr: bool) {
+    if lsr {
+        println!("cargo:rustc-link-lib={}=soxr-lsr", kind);
+    }
+    println!("cargo:rustc-link-lib={}=soxr", kind);
+}
+
+// Where libsoxr ended up, exported to dependent crates through the `links`
+// metadata
+struct Artifacts {
+    include_paths: Vec<PathBuf>,
+    lib_paths: Vec<PathBuf>,
+    statik: bool,
+}
+
+// libsoxr build switches selected through cargo features, only applied to the
+// vendored copy
+struct VendoredOptions {
+    dynamic: bool,
+    lsr: bool,
+    openmp: bool,
+    simd: bool,
+    avx: bool,
+    avfft: bool,
+    pffft: bool,
+}
+
+impl VendoredOptions {
+    fn from_env() -> Self {
+        let feature = |name: &str| env::var_os(format!("CARGO_FEATURE_{}", name)).is_some();
+
+        Self {
+            dynamic: feature("DYNAMIC"),
+            lsr: feature("LSR"),
+            openmp: feature("OPENMP"),
+            simd: feature("SIMD"),
+            avx: feature("AVX"),
+            avfft: feature("FFT_AVFFT"),
+            pffft: feature("FFT_PFFFT"),
+        }
+    }
+}
+
+// A static libsoxr built with OpenMP leaves the runtime for the final link
+fn link_openmp() {
+    let target = env::var("TARGET").unwrap();
+
+    // MSVC object files already reference vcomp
+    if !target.contains("msvc") {
+        println!("cargo:rustc-link-lib={}", if target.contains("apple") { "omp" } else { "gomp" });
+    }
+}
+
+#[cfg(feature = "fft-avfft")]
+fn find_avcodec() -> Vec<PathBuf> {
+    let mut config = pkg_config::Config::new();
+    config.statik(false);
+
+    ["libavcodec", "libavutil"]
+        .iter()
+        .flat_map(|name| config.probe(name).unwrap_or_else(|err| panic!("the `fft-avfft` feature requires {}: {}", name, err)).include_paths)
+        .collect()
+}
+
+#[cfg(not(feature = "fft-avfft"))]
+fn find_avcodec() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+fn find_from_env(lsr: bool) -> Option<Artifacts> {
+    println!("cargo:rerun-if-env-changed=SOXR_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=SOXR_INCLUDE_DIR");
+    println!("cargo:rerun-if-env-changed=SOXR_STATIC");
+
+    let lib_dir = PathBuf::from(env::var_os("SOXR_LIB_DIR")?);
+    let include_dir = env::var_os("SOXR_INCLUDE_DIR").map(PathBuf::from).unwrap_or_else(|| lib_dir.join("../include"));
+
+    if !include_dir.join("soxr.h").exists() {
+        println!("cargo:warning=SOXR_LIB_DIR is set but soxr.h was not found in {}", include_dir.display());
+        return None;
+    }
+
+    let statik = env::var_os("SOXR_STATIC").is_some();
+
+    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    link_libs(if statik { "static" } else { "dylib" }, lsr);
+
+    Some(Artifacts {
+        include_paths: vec![include_dir],
+        lib_paths: vec![lib_dir],
+        statik,
+    })
+}
+
+#[cfg(feature = "system")]
+fn find_from_pkg_config(lsr: bool) -> Option<Artifacts> {
+    let statik = env::var_os("SOXR_STATIC").is_some();
+    let mut config = pkg_config::Config::new();
+    config.statik(statik).cargo_metadata(false);
+
+    // Probe everything before emitting any link flags, so that a partial match
+    // does not leak into the vendored fallback
+    let mut libraries = Vec::new();
+    if lsr {
+        libraries.push(config.probe("soxr-lsr").ok()?);
+    }
+    libraries.push(config.probe("soxr").ok()?);
+
+    let kind = if statik { "static" } else { "dylib" };
+
+    for library in &libraries {
+        for path in &library.link_paths {
+            println!("cargo:rustc-link-search=native={}", path.display());
+        }
+        for lib in &library.libs {
+            println!("cargo:rustc-link-lib={}={}", kind, lib);
+        }
+    }
+
+    Some(Artifacts {
+        include_paths: libraries.iter().flat_map(|library| library.include_paths.clone()).collect(),
+        lib_paths: libraries.into_iter().flat_map(|library| library.link_paths).collect(),
+        statik,
+    })
+}
+
+#[cfg(not(feature = "system"))]
+fn find_from_pkg_config(_lsr: bool) -> Option<Artifacts> {
+    None
+}
+
+#[cfg(not(feature = "cc"))]
+fn build_vendored(options: &VendoredOptions) -> Artifacts {
+    let on_off = |value: bool| if value { "ON" } else { "OFF" };
+
+    link_libs(if options.dynamic { "dylib" } else { "static" }, options.lsr);
+    find_avcodec();
 
     let libs = Config::new("soxr")
-        .define("BUILD_SHARED_LIBS", if dynamic { "ON" } else { "OFF" })
+        .define("BUILD_SHARED_LIBS", on_off(options.dynamic))
         .define("BUILD_TESTS", "OFF")
         .define("WITH_DEV_TRACE", "OFF")
-        .define("WITH_OPENMP", "OFF")
-        .define("WITH_PFFFT", "ON")
-        .define("WITH_LSR_BINDINGS", "OFF")
+        .define("WITH_OPENMP", on_off(options.openmp))
+        .define("WITH_CR32S", on_off(options.simd))
+        .define("WITH_CR64S", on_off(options.avx))
+        .define("WITH_AVFFT", on_off(options.avfft))
+        .define("WITH_PFFFT", on_off(options.pffft))
+        .define("WITH_LSR_BINDINGS", on_off(options.lsr))
         .build();
 
     println!("cargo:rustc-link-search=native={}", libs.join("lib").display());
 
-    let include_path = libs.join("include");
-    println!("cargo:include={}", include_path.display());
+    if options.openmp && !options.dynamic {
+        link_openmp();
+    }
+
+    Artifacts {
+        include_paths: vec![libs.join("include")],
+        lib_paths: vec![libs.join("lib")],
+        statik: !options.dynamic,
+    }
+}
+
+// Mirrors the configuration passed to CMake above, for hosts without CMake
+#[cfg(feature = "cc")]
+fn build_vendored(options: &VendoredOptions) -> Artifacts {
+    if options.dynamic {
+        panic!("the `cc` build only produces a static libsoxr, disable the `dynamic` feature");
+    }
+
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+    let big_endian = env::var("CARGO_CFG_TARGET_ENDIAN").unwrap() == "big";
+    let msvc = env::var("CARGO_CFG_TARGET_ENV").unwrap() == "msvc";
 
-    let builder = bindgen::builder()
-        .header("include/wrapper.h")
-        .clang_arg(format!("-I{}", include_path.display()))
+    let x86 = arch == "x86" || arch == "x86_64";
+    let simd32 = options.simd && (x86 || arch == "aarch64");
+    let simd64 = options.avx && x86;
+    let avcodec_include_paths = find_avcodec();
+
+    let config = [
+        ("AVCODEC_FOUND", options.avfft),
+        ("AVUTIL_FOUND", options.avfft),
+        ("WITH_PFFFT", options.pffft),
+        ("HAVE_FENV_H", true),
+        ("HAVE_STDBOOL_H", true),
+        ("HAVE_STDINT_H", true),
+        ("HAVE_LRINT", true),
+        ("HAVE_BIGENDIAN", big_endian),
+        ("WITH_CR32", true),
+        ("WITH_CR32S", simd32),
+        ("WITH_CR64", true),
+        ("WITH_CR64S", simd64),
+        ("WITH_VR32", true),
+        ("WITH_HI_PREC_CLOCK", true),
+        ("WITH_FLOAT_STD_PREC_CLOCK", false),
+        ("WITH_DEV_TRACE", false),
+    ];
+    let defines: Vec<_> = config.iter().map(|(name, value)| format!("#define {} {}\n", name, *value as u8)).collect();
+    std::fs::write(out_path.join("soxr-config.h"), defines.concat()).unwrap();
+
+    let new_build = || {
+        let mut build = cc::Build::new();
+        build.include(&out_path).include("soxr/src").includes(&avcodec_include_paths).define("SOXR_LIB", None).warnings(false);
+        if msvc {
+            build.define("_USE_MATH_DEFINES", None);
+        }
+        if options.openmp {
+            build.flag(if msvc { "/openmp" } else { "-fopenmp" });
+        }
+        build
+    };
+    let sources = |names: &[&str]| names.iter().map(|name| format!("soxr/src/{}.c", name)).collect::<Vec<_>>();
+
+    let mut build = new_build();
+    build.files(sources(&["soxr", "data-io", "dbesi0", "filter", "fft4g64", "cr", "cr32", "cr64", "vr32"]));
+    build.files(sources(&[if options.avfft { "avfft32" } else { "fft4g32" }]));
+
+    // The SIMD engines are compiled with their own flags, and only selected at
+    // runtime when the CPU supports them
+    let mut objects = Vec::new();
+    if simd32 {
+        let mut simd = new_build();
+        let dft = match (options.avfft, options.pffft) {
+            (true, _) => "avfft32s",
+            (false, true) => "pffft32s",
+            (false, false) => "fft4g32s",
+        };
+        simd.files(sources(&["cr32s", dft, "util32s"]));
+        if x86 && !msvc {
+            simd.flag("-msse");
+        }
+        objects.extend(simd.compile_intermediates());
+    }
+    if simd64 {
+        let mut simd = new_build();
+        simd.files(sources(&["cr64s", "pffft64s", "util64s"]));
+        simd.flag(if msvc { "/arch:AVX" } else { "-mavx" });
+        objects.extend(simd.compile_intermediates());
+    }
+
+    // soxr-lsr depends on soxr, so it has to come first on the link line
+    if options.lsr {
+        new_build().file("soxr/src/lsr.c").compile("soxr-lsr");
+    }
+
+    build.objects(objects).compile("soxr");
+
+    if options.openmp {
+        link_openmp();
+    }
+
+    Artifacts {
+        include_paths: vec![PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("soxr/src")],
+        lib_paths: vec![out_path],
+        statik: true,
+    }
+}
+
+#[cfg(not(feature = "bindgen"))]
+fn header_version(header: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(header).ok()?;
+    let line = content.lines().find(|line| line.trim_start().starts_with("#define") && line.contains("SOXR_THIS_VERSION_STR"))?;
+
+    line.split('"').nth(1).map(str::to_string)
+}
+
+#[cfg(not(feature = "bindgen"))]
+fn bindings_version(bindings: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(bindings).ok()?;
+    let line = content.lines().find(|line| line.contains("pub const SOXR_THIS_VERSION_STR"))?;
+
+    line.split('"').nth(1).map(|version| version.trim_end_matches("\\0").to_string())
+}
+
+// The pregenerated bindings are only valid for the header they were generated
+// from, so refuse to build against anything else
+#[cfg(not(feature = "bindgen"))]
+fn check_bindings_version(include_paths: &[PathBuf]) {
+    let Some(header) = include_paths.iter().map(|path| path.join("soxr.h")).find(|path| path.exists()) else {
+        println!("cargo:warning=soxr.h not found, skipping the bindings version check");
+        return;
+    };
+
+    let expected = bindings_version(Path::new(GENERATED_BINDINGS)).expect("SOXR_THIS_VERSION_STR not found in the pregenerated bindings");
+    let actual = header_version(&header).unwrap_or_else(|| panic!("SOXR_THIS_VERSION_STR not found in {}", header.display()));
+
+    if expected != actual {
+        panic!(
+            "{} is version {}, but the pregenerated bindings are for version {}; enable the `bindgen` feature to regenerate them",
+            header.display(),
+            actual,
+            expected
+        );
+    }
+}
+
+#[cfg(feature = "bindgen")]
+fn generate_bindings(header: &str, include_paths: &[PathBuf], out_path: &Path, lsr: bool) {
+    let mut builder = bindgen::builder()
+        .header(header)
+        .clang_args(include_paths.iter().map(|path| format!("-I{}", path.display())))
         .default_enum_style(Rust {
             non_exhaustive: false,
         })
@@ -39,5 +319,77 @@ fn main() {
         .layout_tests(false)
         .merge_extern_blocks(true);
 
-    builder.generate().unwrap().write_to_file(PathBuf::from(env::var("OUT_DIR").unwrap()).join("soxr.rs")).unwrap();
+    // Keep the output free of platform-specific libc types, so that it can be
+    // checked in as the pregenerated bindings
+    builder = if lsr {
+        builder.allowlist_function("src_.*").allowlist_type("SRC_.*|src_.*")
+    } else {
+        builder.allowlist_function("soxr_.*").allowlist_type("soxr_.*").allowlist_var("SOXR_.*")
+    };
+
+    builder.generate().unwrap().write_to_file(out_path).unwrap();
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=soxr");
+    println!("cargo:rerun-if-changed=include/wrapper.h");
+    println!("cargo:rerun-if-changed=include/wrapper_lsr.h");
+    println!("cargo:rerun-if-changed={}", GENERATED_BINDINGS);
+
+    let options = VendoredOptions::from_env();
+    let lsr = options.lsr;
+    let system = env::var("CARGO_FEATURE_SYSTEM").is_ok();
+    let runtime = env::var("CARGO_FEATURE_RUNTIME").is_ok();
+
+    let system_artifacts = if system && !runtime {
+        find_from_env(lsr).or_else(|| find_from_pkg_config(lsr))
+    } else {
+        None
+    };
+
+    let artifacts = match system_artifacts {
+        // libsoxr is loaded at runtime, so there is nothing to build or link
+        None if runtime => {
+            println!("cargo:source=runtime");
+            Artifacts {
+                include_paths: vec![PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("soxr/src")],
+                lib_paths: Vec::new(),
+                statik: false,
+            }
+        }
+        Some(artifacts) => {
+            let paths: Vec<_> = artifacts.include_paths.iter().map(|path| path.display().to_string()).collect();
+            println!("cargo:warning=using system libsoxr ({})", paths.join(", "));
+            println!("cargo:source=system");
+            artifacts
+        }
+        None => {
+            if system {
+                println!("cargo:warning=system libsoxr not found, building the vendored copy");
+            }
+            println!("cargo:source=vendored");
+            build_vendored(&options)
+        }
+    };
+
+    // Read by dependent crates as DEP_SOXR_INCLUDE, DEP_SOXR_LIB and DEP_SOXR_STATIC
+    println!("cargo:include={}", env::join_paths(&artifacts.include_paths).unwrap().to_string_lossy());
+    println!("cargo:lib={}", env::join_paths(&artifacts.lib_paths).unwrap().to_string_lossy());
+    println!("cargo:static={}", artifacts.statik as u8);
+
+    let include_paths = artifacts.include_paths;
+
+    #[cfg(feature = "bindgen")]
+    {
+        let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+        generate_bindings("include/wrapper.h", &include_paths, &out_path.join("soxr.rs"), false);
+
+        if lsr {
+            generate_bindings("include/wrapper_lsr.h", &include_paths, &out_path.join("soxr_lsr.rs"), true);
+        }
+    }
+
+    #[cfg(not(feature = "bindgen"))]
+    check_bindings_version(&include_paths);
 }
@@ -27,8 +27,6 @@ pub const SOXR_LINEAR_PHASE: u32 = 0;
 pub const SOXR_INTERMEDIATE_PHASE: u32 = 16;
 pub const SOXR_MINIMUM_PHASE: u32 = 48;
 pub const SOXR_STEEP_FILTER: u32 = 64;
-pub type wchar_t = ::std::os::raw::c_ushort;
-pub type max_align_t = f64;
 pub type soxr_io_spec_t = soxr_io_spec;
 pub type soxr_quality_spec_t = soxr_quality_spec;
 pub type soxr_runtime_spec_t = soxr_runtime_spec;
@@ -0,0 +1,59 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type SRC_SAMPLE = f32;
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum SRC_SRCTYPE_e {
+    SRC_SINC_BEST_QUALITY = 0,
+    SRC_SINC_MEDIUM_QUALITY = 1,
+    SRC_SINC_FASTEST = 2,
+    SRC_ZERO_ORDER_HOLD = 3,
+    SRC_LINEAR = 4,
+}
+pub type SRC_SRCTYPE = ::std::os::raw::c_int;
+pub type SRC_ERROR = ::std::os::raw::c_int;
+pub type src_callback_t =
+    ::std::option::Option<unsafe extern "C" fn(arg1: *mut ::std::os::raw::c_void, arg2: *mut *mut SRC_SAMPLE) -> ::std::os::raw::c_long>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct SRC_STATE {
+    _unused: [u8; 0],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct SRC_DATA {
+    pub data_in: *mut SRC_SAMPLE,
+    pub data_out: *mut SRC_SAMPLE,
+    pub input_frames: ::std::os::raw::c_long,
+    pub output_frames: ::std::os::raw::c_long,
+    pub input_frames_used: ::std::os::raw::c_long,
+    pub output_frames_gen: ::std::os::raw::c_long,
+    pub end_of_input: ::std::os::raw::c_int,
+    pub src_ratio: f64,
+}
+unsafe extern "C" {
+    pub fn src_new(arg1: SRC_SRCTYPE, num_channels: ::std::os::raw::c_int, arg2: *mut SRC_ERROR) -> *mut SRC_STATE;
+    pub fn src_process(arg1: *mut SRC_STATE, arg2: *mut SRC_DATA) -> SRC_ERROR;
+    pub fn src_set_ratio(arg1: *mut SRC_STATE, arg2: f64) -> SRC_ERROR;
+    pub fn src_reset(arg1: *mut SRC_STATE) -> SRC_ERROR;
+    pub fn src_error(arg1: *mut SRC_STATE) -> SRC_ERROR;
+    pub fn src_delete(arg1: *mut SRC_STATE) -> *mut SRC_STATE;
+    pub fn src_callback_new(
+        arg1: src_callback_t,
+        arg2: SRC_SRCTYPE,
+        arg3: ::std::os::raw::c_int,
+        arg4: *mut SRC_ERROR,
+        arg5: *mut ::std::os::raw::c_void,
+    ) -> *mut SRC_STATE;
+    pub fn src_callback_read(arg1: *mut SRC_STATE, src_ratio: f64, arg2: ::std::os::raw::c_long, arg3: *mut SRC_SAMPLE) -> ::std::os::raw::c_long;
+    pub fn src_simple(arg1: *mut SRC_DATA, arg2: SRC_SRCTYPE, arg3: ::std::os::raw::c_int) -> SRC_ERROR;
+    pub fn src_get_name(arg1: SRC_SRCTYPE) -> *const ::std::os::raw::c_char;
+    pub fn src_get_description(arg1: SRC_SRCTYPE) -> *const ::std::os::raw::c_char;
+    pub fn src_get_version() -> *const ::std::os::raw::c_char;
+    pub fn src_strerror(arg1: SRC_ERROR) -> *const ::std::os::raw::c_char;
+    pub fn src_is_valid_ratio(arg1: f64) -> ::std::os::raw::c_int;
+    pub fn src_short_to_float_array(arg1: *const ::std::os::raw::c_short, arg2: *mut f32, arg3: ::std::os::raw::c_int);
+    pub fn src_float_to_short_array(arg1: *const f32, arg2: *mut ::std::os::raw::c_short, arg3: ::std::os::raw::c_int);
+    pub fn src_int_to_float_array(arg1: *const ::std::os::raw::c_int, arg2: *mut f32, arg3: ::std::os::raw::c_int);
+    pub fn src_float_to_int_array(arg1: *const f32, arg2: *mut ::std::os::raw::c_int, arg3: ::std::os::raw::c_int);
+}
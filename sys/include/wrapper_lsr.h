@@ -0,0 +1 @@
+#include <soxr-lsr.h>
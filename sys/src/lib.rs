@@ -2,4 +2,34 @@
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
 
+#[cfg(all(feature = "runtime", feature = "lsr"))]
+compile_error!("the `runtime` feature does not support the `lsr` bindings");
+
+#[cfg(all(not(feature = "runtime"), feature = "bindgen"))]
 include!(concat!(env!("OUT_DIR"), "/soxr.rs"));
+#[cfg(all(not(feature = "runtime"), not(feature = "bindgen")))]
+include!("../generated/soxr.rs");
+
+// The extern declarations are never linked; the runtime module shadows them
+// with wrappers calling into the loaded library
+#[cfg(feature = "runtime")]
+#[allow(dead_code)]
+mod bindings {
+    #[cfg(feature = "bindgen")]
+    include!(concat!(env!("OUT_DIR"), "/soxr.rs"));
+    #[cfg(not(feature = "bindgen"))]
+    include!("../generated/soxr.rs");
+}
+
+#[cfg(feature = "runtime")]
+mod runtime;
+#[cfg(feature = "runtime")]
+pub use runtime::*;
+
+#[cfg(feature = "lsr")]
+pub mod lsr {
+    #[cfg(feature = "bindgen")]
+    include!(concat!(env!("OUT_DIR"), "/soxr_lsr.rs"));
+    #[cfg(not(feature = "bindgen"))]
+    include!("../generated/soxr_lsr.rs");
+}
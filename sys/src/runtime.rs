@@ -0,0 +1,175 @@
+use std::{
+    error::Error,
+    ffi::OsStr,
+    fmt,
+    os::raw::{c_char, c_uint, c_ulong, c_void},
+    sync::{Mutex, OnceLock, PoisonError},
+};
+
+use libloading::Library;
+
+pub use crate::bindings::*;
+
+#[cfg(windows)]
+const LIBRARY_NAMES: &[&str] = &["soxr.dll", "libsoxr.dll"];
+#[cfg(target_vendor = "apple")]
+const LIBRARY_NAMES: &[&str] = &["libsoxr.0.dylib", "libsoxr.dylib"];
+#[cfg(not(any(windows, target_vendor = "apple")))]
+const LIBRARY_NAMES: &[&str] = &["libsoxr.so.0", "libsoxr.so"];
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// A library was loaded before, so the requested one would be ignored.
+    AlreadyLoaded,
+    Library(libloading::Error),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::AlreadyLoaded => write!(f, "libsoxr already loaded"),
+            LoadError::Library(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::AlreadyLoaded => None,
+            LoadError::Library(err) => Some(err),
+        }
+    }
+}
+
+impl From<libloading::Error> for LoadError {
+    fn from(err: libloading::Error) -> Self {
+        LoadError::Library(err)
+    }
+}
+
+macro_rules! functions {
+    ($(fn $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)?;)*) => {
+        struct Functions {
+            $($name: unsafe extern "C" fn($($ty),*) $(-> $ret)?,)*
+            _library: Library,
+        }
+
+        impl Functions {
+            unsafe fn load(library: Library) -> Result<Self, LoadError> {
+                Ok(Self {
+                    $($name: *unsafe { library.get(concat!(stringify!($name), "\0").as_bytes())? },)*
+                    _library: library,
+                })
+            }
+        }
+
+        $(
+            /// Calls the function of the same name in the loaded libsoxr.
+            ///
+            /// # Safety
+            ///
+            /// Same as the C function. Panics if libsoxr has not been loaded.
+            #[allow(clippy::too_many_arguments)]
+            pub unsafe fn $name($($arg: $ty),*) $(-> $ret)? {
+                unsafe { (functions().$name)($($arg),*) }
+            }
+        )*
+    };
+}
+
+functions! {
+    fn soxr_version() -> *const c_char;
+    fn soxr_create(
+        input_rate: f64,
+        output_rate: f64,
+        num_channels: c_uint,
+        error: *mut soxr_error_t,
+        io_spec: *const soxr_io_spec_t,
+        quality_spec: *const soxr_quality_spec_t,
+        runtime_spec: *const soxr_runtime_spec_t
+    ) -> soxr_t;
+    fn soxr_process(
+        resampler: soxr_t,
+        in_: soxr_in_t,
+        ilen: usize,
+        idone: *mut usize,
+        out: soxr_out_t,
+        olen: usize,
+        odone: *mut usize
+    ) -> soxr_error_t;
+    fn soxr_set_input_fn(resampler: soxr_t, input_fn: soxr_input_fn_t, input_fn_state: *mut c_void, max_ilen: usize) -> soxr_error_t;
+    fn soxr_output(resampler: soxr_t, data: soxr_out_t, olen: usize) -> usize;
+    fn soxr_error(resampler: soxr_t) -> soxr_error_t;
+    fn soxr_num_clips(resampler: soxr_t) -> *mut usize;
+    fn soxr_delay(resampler: soxr_t) -> f64;
+    fn soxr_engine(resampler: soxr_t) -> *const c_char;
+    fn soxr_clear(resampler: soxr_t) -> soxr_error_t;
+    fn soxr_delete(resampler: soxr_t);
+    fn soxr_oneshot(
+        input_rate: f64,
+        output_rate: f64,
+        num_channels: c_uint,
+        in_: soxr_in_t,
+        ilen: usize,
+        idone: *mut usize,
+        out: soxr_out_t,
+        olen: usize,
+        odone: *mut usize,
+        io_spec: *const soxr_io_spec_t,
+        quality_spec: *const soxr_quality_spec_t,
+        runtime_spec: *const soxr_runtime_spec_t
+    ) -> soxr_error_t;
+    fn soxr_set_io_ratio(resampler: soxr_t, io_ratio: f64, slew_len: usize) -> soxr_error_t;
+    fn soxr_quality_spec(recipe: c_ulong, flags: c_ulong) -> soxr_quality_spec_t;
+    fn soxr_runtime_spec(num_threads: c_uint) -> soxr_runtime_spec_t;
+    fn soxr_io_spec(itype: soxr_datatype_t, otype: soxr_datatype_t) -> soxr_io_spec_t;
+    fn soxr_set_error(resampler: soxr_t, error: soxr_error_t) -> soxr_error_t;
+    fn soxr_set_num_channels(resampler: soxr_t, num_channels: c_uint) -> soxr_error_t;
+}
+
+static FUNCTIONS: OnceLock<Functions> = OnceLock::new();
+static LOAD_LOCK: Mutex<()> = Mutex::new(());
+
+fn functions() -> &'static Functions {
+    FUNCTIONS.get().expect("libsoxr is not loaded")
+}
+
+pub fn is_loaded() -> bool {
+    FUNCTIONS.get().is_some()
+}
+
+/// Loads libsoxr from `path` and resolves all of its functions.
+///
+/// The first library loaded successfully stays in use for the lifetime of the
+/// process; later calls fail with [`LoadError::AlreadyLoaded`].
+pub fn load_from<P: AsRef<OsStr>>(path: P) -> Result<(), LoadError> {
+    let _guard = LOAD_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    if is_loaded() {
+        return Err(LoadError::AlreadyLoaded);
+    }
+
+    let functions = unsafe { Functions::load(Library::new(path)?)? };
+    let _ = FUNCTIONS.set(functions);
+
+    Ok(())
+}
+
+/// Loads libsoxr from the platform's default library search path, unless a
+/// library is already loaded.
+pub fn load() -> Result<(), LoadError> {
+    let mut result = Ok(());
+
+    for name in LIBRARY_NAMES {
+        result = match load_from(name) {
+            Err(LoadError::AlreadyLoaded) => Ok(()),
+            result => result,
+        };
+        if result.is_ok() {
+            break;
+        }
+    }
+
+    result
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "soxr_lsr")]
+
+use media_soxr::{lsr::*, ErrorKind};
+
+#[test]
+fn test_simple() {
+    let input: Vec<f32> = vec![0.0; 44100 * 2];
+    let mut output: Vec<f32> = vec![0.0; 48000 * 2];
+
+    let mut data = SrcData::new(&input, &mut output, 48000.0 / 44100.0);
+    data.end_of_input = true;
+
+    simple(&mut data, ConverterType::SincBestQuality, 2).unwrap();
+
+    assert_eq!(data.input_frames_used, 44100);
+    assert!(data.output_frames_gen > 0);
+
+    let mut data = SrcData::new(&input[..44100 * 2 - 1], &mut output, 48000.0 / 44100.0);
+    let err = simple(&mut data, ConverterType::SincBestQuality, 2).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::PartialFrame {
+            len: 44100 * 2 - 1,
+            channels: 2,
+        }
+    );
+}
+
+#[test]
+fn test_converter() {
+    assert!(is_valid_ratio(2.0));
+
+    let mut converter = Converter::new(ConverterType::SincMediumQuality, 1).unwrap();
+
+    let input: Vec<f32> = vec![0.0; 1024];
+    let mut output: Vec<f32> = vec![0.0; 4096];
+    let mut total = 0;
+
+    for (i, ratio) in [1.0, 1.5, 2.0].into_iter().enumerate() {
+        let mut data = SrcData::new(&input, &mut output, ratio);
+        data.end_of_input = i == 2;
+
+        converter.process(&mut data).unwrap();
+
+        assert!(data.input_frames_used <= input.len());
+        total += data.output_frames_gen;
+    }
+
+    assert!(total > 0);
+
+    let mut converter = Converter::new(ConverterType::SincMediumQuality, 2).unwrap();
+    let mut data = SrcData::new(&input, &mut output[..4095], 1.0);
+    assert!(converter.process(&mut data).is_err());
+
+    converter.set_ratio(0.5).unwrap();
+    converter.reset().unwrap();
+}
+
+#[test]
+fn test_callback_converter() {
+    let mut remaining = 4800;
+    let mut converter = CallbackConverter::new(ConverterType::SincFastest, 2, 256, move |buf: &mut [f32]| {
+        let frames = (buf.len() / 2).min(remaining);
+        buf[..frames * 2].fill(0.0);
+        remaining -= frames;
+        frames
+    })
+    .unwrap();
+
+    let mut output: Vec<f32> = vec![0.0; 512 * 2];
+    let mut total = 0;
+
+    loop {
+        let frames = converter.read(2.0, &mut output).unwrap();
+        if frames == 0 {
+            break;
+        }
+        total += frames;
+    }
+
+    assert!(total > 4800);
+}
@@ -0,0 +1,23 @@
+#![cfg(feature = "soxr_runtime")]
+
+use media_soxr::*;
+
+#[test]
+fn test_load() {
+    let err = runtime::load_from("/nonexistent/libsoxr.so").err().unwrap();
+    assert!(matches!(err.kind(), ErrorKind::LoadLibrary(_)));
+    assert!(!runtime::is_loaded());
+
+    // Creating a spec loads the default library, after which a chosen path
+    // can no longer take effect
+    QualitySpec::new(QualityRecipe::High, QualityFlags::empty()).unwrap();
+    assert!(runtime::is_loaded());
+
+    let err = runtime::load_from("/nonexistent/libsoxr.so").err().unwrap();
+    assert_eq!(err.kind(), &ErrorKind::LoadLibrary("libsoxr already loaded".to_string()));
+
+    runtime::load().unwrap();
+
+    let soxr = Soxr::<Packed<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, None, None);
+    assert!(soxr.is_ok());
+}
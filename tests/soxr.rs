@@ -1,12 +1,35 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
 use media_soxr::*;
 
 #[test]
 fn test_version() {
-    let version = Soxr::<Packed<f32>, Packed<f32>>::version();
+    let version = Soxr::<Packed<f32>, Packed<f32>>::version().unwrap();
     println!("soxr version: {}", version);
     assert!(!version.is_empty());
 }
 
+#[test]
+fn test_version_parse() {
+    assert_eq!(HEADER_VERSION, "0.1.3".parse().unwrap());
+    assert_eq!(HEADER_VERSION.to_string(), "0.1.3");
+    assert!("0.1".parse::<Version>().is_err());
+    assert!("0.1.3.4".parse::<Version>().is_err());
+    assert_eq!(Version::parse_runtime("libsoxr-0.1.3").unwrap(), Version::new(0, 1, 3));
+    assert_eq!(Version::parse_runtime("libsoxr-0.1.3-2").unwrap(), Version::new(0, 1, 3));
+    assert_eq!(Version::parse_runtime("0.1.3~rc1").unwrap(), Version::new(0, 1, 3));
+    assert!(Version::parse_runtime("libsoxr-0.1").is_err());
+    assert!(Version::new(0, 1, 2).is_compatible_with(&Version::new(0, 1, 3)));
+    assert!(!Version::new(0, 2, 0).is_compatible_with(&Version::new(0, 1, 3)));
+
+    let runtime = Version::runtime().unwrap();
+    println!("soxr runtime version: {}", runtime);
+    assert!(runtime.is_compatible_with(&HEADER_VERSION));
+}
+
 #[test]
 fn test_new() {
     let soxr = Soxr::<Packed<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, None, None);
@@ -39,3 +62,772 @@ fn test_process() {
 
     assert_eq!(idone, 44100);
 }
+
+#[test]
+fn test_oneshot() {
+    let input: &[&[f32]] = &[&vec![0.0; 44100], &vec![0.0; 44100]];
+    let mut output: Vec<f32> = vec![0.0; 48000 * 2];
+
+    let result =
+        Soxr::<Planar<f32>, Packed<f32>>::oneshot(44100.0, 48000.0, 2, SampleBuffer::Planar(input), SampleBufferMut::Packed(&mut output), None, None);
+
+    assert!(result.is_ok());
+
+    let (idone, odone) = result.unwrap();
+
+    assert_eq!(idone, 44100);
+    assert_eq!(odone, 48000);
+
+    let input: &[&[f32]] = &[&vec![0.0; 44100]];
+    let result =
+        Soxr::<Planar<f32>, Packed<f32>>::oneshot(44100.0, 48000.0, 2, SampleBuffer::Planar(input), SampleBufferMut::Packed(&mut output), None, None);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_output() {
+    let mut soxr = Soxr::<Packed<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, None, None).unwrap();
+
+    let remaining = Arc::new(AtomicUsize::new(44100));
+    let source_remaining = remaining.clone();
+    soxr.set_input_fn(
+        move |buffer: SampleBufferMut<Packed<f32>>| {
+            let SampleBufferMut::Packed(buf) = buffer else {
+                unreachable!();
+            };
+            let len = (buf.len() / 2).min(source_remaining.load(Ordering::SeqCst));
+            buf[..len * 2].fill(0.0);
+            source_remaining.fetch_sub(len, Ordering::SeqCst);
+            Ok(len)
+        },
+        1024,
+    )
+    .unwrap();
+
+    let mut output: Vec<f32> = vec![0.0; 480 * 2];
+    let mut total = 0;
+
+    loop {
+        let odone = soxr.output(SampleBufferMut::Packed(&mut output)).unwrap();
+        if odone == 0 {
+            break;
+        }
+        total += odone;
+    }
+
+    assert_eq!(total, 48000);
+
+    // Clearing after the end of input restarts pull mode
+    soxr.clear().unwrap();
+    remaining.store(44100, Ordering::SeqCst);
+    assert!(soxr.output(SampleBufferMut::Packed(&mut output)).unwrap() > 0);
+}
+
+#[test]
+fn test_output_error() {
+    let mut soxr = Soxr::<Planar<f32>, Planar<f32>>::new(44100.0, 48000.0, 2, None, None).unwrap();
+
+    let mut left = vec![0.0; 480];
+    let mut right = vec![0.0; 480];
+
+    assert_eq!(soxr.output(SampleBufferMut::Planar(&mut [&mut left, &mut right])).unwrap_err().kind(), &ErrorKind::InputFnNotSet);
+
+    soxr.set_input_fn(|_: SampleBufferMut<Planar<f32>>| Err(Error::other("source failure")), 1024).unwrap();
+
+    let result = soxr.output(SampleBufferMut::Planar(&mut [&mut left, &mut right]));
+
+    assert_eq!(result.unwrap_err().kind(), &ErrorKind::Other("source failure".to_string()));
+
+    let mut remaining = 4096;
+    soxr.set_input_fn(
+        move |buffer: SampleBufferMut<Planar<f32>>| {
+            let SampleBufferMut::Planar(bufs) = buffer else {
+                unreachable!();
+            };
+            if remaining == 0 {
+                return Err(Error::other("source failure"));
+            }
+            let len = bufs[0].len().min(remaining);
+            remaining -= len;
+            Ok(len)
+        },
+        1024,
+    )
+    .unwrap();
+
+    let mut left = vec![0.0; 48000];
+    let mut right = vec![0.0; 48000];
+
+    // The frames resampled before the failure come first
+    assert!(soxr.output(SampleBufferMut::Planar(&mut [&mut left, &mut right])).unwrap() > 0);
+
+    let result = soxr.output(SampleBufferMut::Planar(&mut [&mut left, &mut right]));
+    assert_eq!(result.unwrap_err().kind(), &ErrorKind::Other("source failure".to_string()));
+}
+
+#[test]
+fn test_quality_spec_builder() {
+    let spec = QualitySpec::builder(QualityRecipe::High)
+        .flags(QualityFlags::RolloffNone)
+        .precision(24.0)
+        .phase_response(25.0)
+        .passband_end(0.9)
+        .stopband_begin(1.05)
+        .build()
+        .unwrap();
+
+    assert_eq!(spec.precision(), 24.0);
+    assert_eq!(spec.phase_response(), 25.0);
+    assert_eq!(spec.passband_end(), 0.9);
+    assert_eq!(spec.stopband_begin(), 1.05);
+    assert!(spec.flags().contains(QualityFlags::RolloffNone));
+
+    assert!(Soxr::<Packed<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, Some(&spec.clone()), None).is_ok());
+
+    assert!(QualitySpec::builder(QualityRecipe::High).precision(40.0).build().is_err());
+    assert!(QualitySpec::builder(QualityRecipe::High).phase_response(101.0).build().is_err());
+    assert!(QualitySpec::builder(QualityRecipe::High).passband_end(1.0).build().is_err());
+    assert!(QualitySpec::builder(QualityRecipe::High).passband_end(0.95).stopband_begin(0.9).build().is_err());
+}
+
+#[test]
+fn test_quality_recipes() {
+    let recipes = [
+        QualityRecipe::Quick,
+        QualityRecipe::Low,
+        QualityRecipe::Medium,
+        QualityRecipe::Bits16,
+        QualityRecipe::High,
+        QualityRecipe::Bits24,
+        QualityRecipe::VeryHigh,
+        QualityRecipe::Bits32,
+        QualityRecipe::Lsr0,
+        QualityRecipe::Lsr1,
+        QualityRecipe::Lsr2,
+    ];
+
+    for recipe in recipes {
+        let spec = QualitySpec::new(recipe, QualityFlags::empty()).unwrap();
+        let soxr = Soxr::<Packed<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, Some(&spec), None);
+
+        assert!(soxr.is_ok(), "{:?}", recipe);
+    }
+}
+
+#[test]
+fn test_phase_response() {
+    let linear = QualitySpec::builder(QualityRecipe::VeryHigh).phase(PhaseResponse::Linear).steep_filter(true).build().unwrap();
+    let intermediate = QualitySpec::builder(QualityRecipe::High).phase(PhaseResponse::Intermediate).build().unwrap();
+    let minimum = QualitySpec::builder(QualityRecipe::High).phase(PhaseResponse::Minimum).build().unwrap();
+
+    assert_eq!(linear.phase_response(), 50.0);
+    assert_ne!(intermediate.phase_response(), linear.phase_response());
+    assert_ne!(minimum.phase_response(), linear.phase_response());
+    assert_ne!(minimum.phase_response(), intermediate.phase_response());
+
+    for spec in [&linear, &intermediate, &minimum] {
+        assert!(Soxr::<Packed<f32>, Packed<f32>>::new(48000.0, 44100.0, 2, Some(spec), None).is_ok());
+    }
+}
+
+#[test]
+fn test_runtime_spec_builder() {
+    let spec = RuntimeSpec::new(4);
+    assert_eq!(spec.num_threads(), 4);
+    assert_eq!(spec.log2_min_dft_size(), 10);
+    assert_eq!(spec.log2_large_dft_size(), 17);
+    assert_eq!(spec.coef_size_kbytes(), 400);
+    assert_eq!(spec.coef_interpolation(), CoefInterpolation::Auto);
+
+    let spec = RuntimeSpec::builder()
+        .num_threads(2)
+        .log2_min_dft_size(9)
+        .log2_large_dft_size(16)
+        .coef_size_kbytes(100)
+        .coef_interpolation(CoefInterpolation::High)
+        .build()
+        .unwrap();
+
+    assert_eq!(spec.num_threads(), 2);
+    assert_eq!(spec.log2_min_dft_size(), 9);
+    assert_eq!(spec.log2_large_dft_size(), 16);
+    assert_eq!(spec.coef_size_kbytes(), 100);
+    assert_eq!(spec.coef_interpolation(), CoefInterpolation::High);
+
+    let quality_spec = QualitySpec::new(QualityRecipe::High, QualityFlags::VR).unwrap();
+    assert!(Soxr::<Packed<f32>, Packed<f32>>::new(1.0, 1.0, 2, Some(&quality_spec), Some(&spec)).is_ok());
+
+    assert!(RuntimeSpec::builder().log2_min_dft_size(16).build().is_err());
+    assert!(RuntimeSpec::builder().log2_large_dft_size(21).build().is_err());
+    assert!(RuntimeSpec::builder().log2_min_dft_size(12).log2_large_dft_size(10).build().is_err());
+    assert!(RuntimeSpec::builder().coef_size_kbytes(0).build().is_err());
+}
+
+#[test]
+fn test_io_spec() {
+    let io_spec = IOSpec::builder(DataType::Float32I, DataType::Int16I).gain_db(-6.0).dither(Dither::None).build().unwrap();
+
+    assert!((io_spec.scale() - 0.501).abs() < 0.001);
+    assert_eq!(io_spec.dither(), Dither::None);
+
+    // Half scale, so the unity gain run does not clip
+    let input: Vec<f32> = (0..4410 * 2).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+    let unity_spec = IOSpec::builder(DataType::Float32I, DataType::Int16I).dither(Dither::None).build().unwrap();
+    let mut outputs = Vec::new();
+
+    for spec in [&io_spec, &io_spec, &unity_spec] {
+        let mut soxr = Soxr::<Packed<f32>, Packed<i16>>::new_with_io_spec(44100.0, 48000.0, 2, spec, None, None).unwrap();
+        let mut output: Vec<i16> = vec![0; 4800 * 2];
+
+        soxr.process(Some(SampleBuffer::Packed(&input)), SampleBufferMut::Packed(&mut output)).unwrap();
+        outputs.push(output);
+    }
+
+    assert_eq!(outputs[0], outputs[1]);
+
+    let peak = |output: &[i16]| output.iter().map(|sample| (*sample as i32).abs()).max().unwrap() as f64;
+    assert!(peak(&outputs[2]) > 0.0);
+    assert!((peak(&outputs[0]) / peak(&outputs[2]) - io_spec.scale()).abs() < 0.01);
+
+    assert!(Soxr::<Packed<f32>, Packed<f32>>::new_with_io_spec(44100.0, 48000.0, 2, &io_spec, None, None).is_err());
+    assert!(IOSpec::builder(DataType::Float32I, DataType::Int16I).scale(f64::NAN).build().is_err());
+}
+
+#[test]
+fn test_builder() {
+    let runtime_spec = RuntimeSpec::builder().coef_interpolation(CoefInterpolation::High).build().unwrap();
+    let mut soxr = Soxr::<Packed<f32>, Packed<i16>>::builder(44100.0, 48000.0)
+        .channels(2)
+        .quality(QualityRecipe::High)
+        .variable_rate(true)
+        .runtime_spec(runtime_spec)
+        .gain_db(-6.0)
+        .dither(Dither::None)
+        .build()
+        .unwrap();
+    assert!(soxr.engine().unwrap().is_variable_rate());
+    soxr.set_io_ratio(1.1, 100).unwrap();
+
+    let input = vec![0.0; 2048];
+    let mut output = vec![0; 4096];
+    let (idone, _) = soxr.process(Some(SampleBuffer::Packed(&input)), SampleBufferMut::Packed(&mut output)).unwrap();
+    assert_eq!(idone, 1024);
+
+    // An explicit spec keeps its own flags and ignores the builder's
+    let spec = QualitySpec::new(QualityRecipe::High, QualityFlags::DoublePrecision).unwrap();
+    let soxr = Soxr::<Packed<f32>, Packed<f32>>::builder(44100.0, 48000.0).quality_spec(spec).build().unwrap();
+    assert!(soxr.engine().unwrap().is_double_precision());
+
+    let spec = QualitySpec::new(QualityRecipe::High, QualityFlags::empty()).unwrap();
+    let soxr =
+        Soxr::<Packed<f32>, Packed<f32>>::builder(44100.0, 48000.0).quality_flags(QualityFlags::DoublePrecision).quality_spec(spec).build().unwrap();
+    assert!(!soxr.engine().unwrap().is_double_precision());
+
+    let soxr = Soxr::<DynamicSample, DynamicSample>::builder(44100.0, 48000.0).data_types(DataType::Int32S, DataType::Float64I).build();
+    assert!(soxr.is_ok());
+
+    let err = Soxr::<DynamicSample, DynamicSample>::builder(44100.0, 48000.0).build().err().unwrap();
+    assert_eq!(err.kind(), &ErrorKind::UnsupportedDataType(DataType::Dynamic));
+
+    let err = Soxr::<Packed<f32>, Packed<f32>>::builder(44100.0, 48000.0).data_types(DataType::Float32I, DataType::Int16I).build().err().unwrap();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::TypeMismatch {
+            expected: DataType::Float32I,
+            actual: DataType::Int16I,
+        }
+    );
+
+    for (input_rate, output_rate) in [(0.0, 48000.0), (44100.0, -1.0), (f64::NAN, 48000.0), (44100.0, f64::INFINITY)] {
+        let err = Soxr::<Packed<f32>, Packed<f32>>::builder(input_rate, output_rate).build().err().unwrap();
+        assert!(matches!(err.kind(), ErrorKind::InvalidArgument(_)));
+    }
+
+    let err = Soxr::<Packed<f32>, Packed<f32>>::builder(44100.0, 48000.0).scale(f64::NAN).build().err().unwrap();
+    assert!(matches!(err.kind(), ErrorKind::InvalidSpec(_)));
+}
+
+#[test]
+fn test_error() {
+    let input: &[&[f32]] = &[&vec![0.0; 1024]];
+    let mut output: Vec<f32> = vec![0.0; 1024 * 2];
+
+    let mut soxr = Soxr::<Planar<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, None, None).unwrap();
+    let err = soxr.process(Some(SampleBuffer::Planar(input)), SampleBufferMut::Packed(&mut output)).unwrap_err();
+
+    assert_eq!(err.kind(), &ErrorKind::InvalidChannels(1));
+    assert_eq!(
+        err.context(),
+        Some(&ErrorContext {
+            input_rate: 44100.0,
+            output_rate: 48000.0,
+            channels: 2,
+        })
+    );
+
+    let err = Soxr::<DynamicSample, DynamicSample>::new(44100.0, 48000.0, 2, None, None).err().unwrap();
+    assert_eq!(err.kind(), &ErrorKind::UnsupportedDataType(DataType::Dynamic));
+
+    let mut bytes = vec![0u8; 16];
+    let err = Soxr::<DynamicSample, DynamicSample>::oneshot(
+        44100.0,
+        48000.0,
+        2,
+        SampleBuffer::Packed(&[0; 16]),
+        SampleBufferMut::Packed(&mut bytes),
+        None,
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::UnsupportedDataType(DataType::Dynamic));
+    assert_eq!(err.context().unwrap().channels, 2);
+
+    let err = Soxr::<Packed<f32>, Packed<f32>>::new(44100.0, 48000.0, 0, None, None).err().unwrap();
+    assert_eq!(err.kind(), &ErrorKind::InvalidChannels(0));
+
+    let err: Box<dyn std::error::Error + Send + Sync> = Box::new(err);
+    assert!(!err.to_string().is_empty());
+}
+
+#[test]
+fn test_planar_validation() {
+    let mut soxr = Soxr::<Planar<f32>, Planar<f32>>::new(44100.0, 48000.0, 2, None, None).unwrap();
+    let mut left = vec![0.0; 2048];
+    let mut right = vec![0.0; 2048];
+
+    // Second input channel shorter than the first
+    let input: &[&[f32]] = &[&vec![0.0; 1024], &vec![0.0; 512]];
+    let output: &mut [&mut [f32]] = &mut [&mut left, &mut right];
+    let err = soxr.process(Some(SampleBuffer::Planar(input)), SampleBufferMut::Planar(output)).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::ChannelLengthMismatch {
+            channel: 1,
+            expected: 1024,
+            actual: 512,
+        }
+    );
+
+    // Second output channel shorter than the first
+    let input: &[&[f32]] = &[&vec![0.0; 1024], &vec![0.0; 1024]];
+    let mut short = vec![0.0; 16];
+    let output: &mut [&mut [f32]] = &mut [&mut left, &mut short];
+    let err = soxr.process(Some(SampleBuffer::Planar(input)), SampleBufferMut::Planar(output)).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::ChannelLengthMismatch {
+            channel: 1,
+            expected: 2048,
+            actual: 16,
+        }
+    );
+
+    // No channels at all
+    let output: &mut [&mut [f32]] = &mut [&mut left, &mut right];
+    let err = soxr.process(Some(SampleBuffer::Planar(&[])), SampleBufferMut::Planar(output)).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::InvalidChannels(0));
+
+    let err = soxr.process(None, SampleBufferMut::Planar(&mut [])).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::InvalidChannels(0));
+
+    // Equal lengths, including empty channels, are accepted
+    let input: &[&[f32]] = &[&[], &[]];
+    let output: &mut [&mut [f32]] = &mut [&mut left, &mut right];
+    assert!(soxr.process(Some(SampleBuffer::Planar(input)), SampleBufferMut::Planar(output)).is_ok());
+}
+
+#[test]
+fn test_packed_validation() {
+    let mut soxr = Soxr::<Packed<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, None, None).unwrap();
+    let mut output = vec![0.0; 2048];
+
+    let input = vec![0.0; 1023];
+    let err = soxr.process(Some(SampleBuffer::Packed(&input)), SampleBufferMut::Packed(&mut output)).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::PartialFrame {
+            len: 1023,
+            channels: 2,
+        }
+    );
+
+    let input = vec![0.0; 1024];
+    let mut odd = vec![0.0; 2047];
+    let err = soxr.process(Some(SampleBuffer::Packed(&input)), SampleBufferMut::Packed(&mut odd)).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::PartialFrame {
+            len: 2047,
+            channels: 2,
+        }
+    );
+
+    let (idone, _) = soxr.process(Some(SampleBuffer::Packed(&input)), SampleBufferMut::Packed(&mut output)).unwrap();
+    assert_eq!(idone, 512);
+}
+
+#[test]
+fn test_layout_validation() {
+    let mut left = vec![0.0; 1024];
+    let mut right = vec![0.0; 1024];
+
+    // Planar buffers passed to a packed resampler
+    let mut soxr = Soxr::<Packed<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, None, None).unwrap();
+    let input: &[&[f32]] = &[&vec![0.0; 512], &vec![0.0; 512]];
+    let mut output = vec![0.0; 2048];
+    let err = soxr.process(Some(SampleBuffer::Planar(input)), SampleBufferMut::Packed(&mut output)).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::LayoutMismatch(DataType::Float32I));
+
+    let output: &mut [&mut [f32]] = &mut [&mut left, &mut right];
+    let err = soxr.process(None, SampleBufferMut::Planar(output)).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::LayoutMismatch(DataType::Float32I));
+
+    // process_dynamic checks the buffers against the configured types
+    let mut output = vec![0.0f64; 2048];
+    let err = soxr.process_dynamic::<Packed<f32>, Packed<f64>>(None, SampleBufferMut::Packed(&mut output)).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::TypeMismatch {
+            expected: DataType::Float32I,
+            actual: DataType::Float64I,
+        }
+    );
+}
+
+#[test]
+fn test_dynamic_buffer() {
+    let mut soxr =
+        Soxr::<DynamicSample, DynamicSample>::new_with_data_type(DataType::Float32I, DataType::Int16S, 44100.0, 48000.0, 2, None, None).unwrap();
+
+    let input = vec![0.0f32; 2048];
+    let mut left = vec![0i16; 1024];
+    let mut right = vec![0i16; 1024];
+    let output: &mut [&mut [i16]] = &mut [&mut left, &mut right];
+    let (idone, _) = soxr.process_buffer(Some(DynamicBuffer::PackedF32(&input)), DynamicBufferMut::PlanarI16(output)).unwrap();
+    assert_eq!(idone, 1024);
+
+    let output: &mut [&mut [i16]] = &mut [&mut left, &mut right];
+    soxr.process_buffer(None, DynamicBufferMut::PlanarI16(output)).unwrap();
+
+    let input = vec![0.0f64; 2048];
+    let output: &mut [&mut [i16]] = &mut [&mut left, &mut right];
+    let err = soxr.process_buffer(Some(DynamicBuffer::PackedF64(&input)), DynamicBufferMut::PlanarI16(output)).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::TypeMismatch {
+            expected: DataType::Float32I,
+            actual: DataType::Float64I,
+        }
+    );
+
+    let mut output = vec![0i16; 2048];
+    let err = soxr.process_buffer(None, DynamicBufferMut::PackedI16(&mut output)).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::TypeMismatch {
+            expected: DataType::Int16S,
+            actual: DataType::Int16I,
+        }
+    );
+}
+
+#[test]
+fn test_byte_buffer() {
+    let mut soxr =
+        Soxr::<DynamicSample, DynamicSample>::new_with_data_type(DataType::Int16I, DataType::Float32S, 44100.0, 48000.0, 2, None, None).unwrap();
+
+    // Over-allocate so the buffers can start at an aligned offset
+    let input = vec![0u8; 4096 + 8];
+    let offset = input.as_ptr().align_offset(8);
+    let input = &input[offset..offset + 4096];
+    let mut output = vec![0u8; 2 * 4096 + 16];
+    let offset = output.as_ptr().align_offset(8);
+    let (left, right) = output[offset..offset + 2 * 4096].split_at_mut(4096);
+
+    let (idone, _) = soxr
+        .process_buffer(
+            Some(DynamicBuffer::Bytes(DataType::Int16I, &[input])),
+            DynamicBufferMut::Bytes(DataType::Float32S, &mut [&mut *left, &mut *right]),
+        )
+        .unwrap();
+    assert_eq!(idone, 1024);
+
+    let err = soxr
+        .process_buffer(
+            Some(DynamicBuffer::Bytes(DataType::Int16I, &[&input[..4094]])),
+            DynamicBufferMut::Bytes(DataType::Float32S, &mut [&mut *left, &mut *right]),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::InvalidByteLength {
+            len: 4094,
+            frame_size: 4,
+        }
+    );
+
+    let err = soxr
+        .process_buffer(
+            Some(DynamicBuffer::Bytes(DataType::Int16I, &[&input[1..4093]])),
+            DynamicBufferMut::Bytes(DataType::Float32S, &mut [&mut *left, &mut *right]),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::MisalignedBuffer {
+            align: 2,
+        }
+    );
+
+    // Empty buffers are not checked for alignment
+    let (idone, _) = soxr
+        .process_buffer(
+            Some(DynamicBuffer::Bytes(DataType::Int16I, &[&[]])),
+            DynamicBufferMut::Bytes(DataType::Float32S, &mut [&mut *left, &mut *right]),
+        )
+        .unwrap();
+    assert_eq!(idone, 0);
+
+    // Packed data is a single plane
+    let err = soxr
+        .process_buffer(
+            Some(DynamicBuffer::Bytes(DataType::Int16I, &[input, input])),
+            DynamicBufferMut::Bytes(DataType::Float32S, &mut [&mut *left, &mut *right]),
+        )
+        .unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::LayoutMismatch(DataType::Int16I));
+
+    let err = soxr
+        .process_buffer(
+            Some(DynamicBuffer::Bytes(DataType::Float32I, &[input])),
+            DynamicBufferMut::Bytes(DataType::Float32S, &mut [&mut *left, &mut *right]),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::TypeMismatch {
+            expected: DataType::Int16I,
+            actual: DataType::Float32I,
+        }
+    );
+
+    let err = soxr.process_buffer(None, DynamicBufferMut::Bytes(DataType::Float32S, &mut [left, &mut right[..4092]])).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::ChannelLengthMismatch {
+            channel: 1,
+            expected: 1024,
+            actual: 1023,
+        }
+    );
+}
+
+#[test]
+fn test_fixed() {
+    let mut soxr = FixedSoxr::<Packed<f32>, Planar<i16>, 2>::new(44100.0, 48000.0, None, None).unwrap();
+
+    let input = vec![[0.0f32; 2]; 1024];
+    let mut left = vec![0i16; 2048];
+    let mut right = vec![0i16; 2048];
+    let (idone, _) = soxr.process(Some(&input), [&mut left, &mut right]).unwrap();
+    assert_eq!(idone, 1024);
+
+    let err = soxr.process(None, [&mut left, &mut right[..1024]]).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::ChannelLengthMismatch {
+            channel: 1,
+            expected: 2048,
+            actual: 1024,
+        }
+    );
+
+    let mut total = 0;
+    loop {
+        let (_, odone) = soxr.process(None, [&mut left, &mut right]).unwrap();
+        if odone == 0 {
+            break;
+        }
+        total += odone;
+    }
+    assert!(total > 0);
+
+    let mut soxr = Soxr::<Planar<f64>, Packed<f64>>::builder(44100.0, 48000.0).channels(1).build_fixed::<6>().unwrap();
+    let input = vec![0.0f64; 480];
+    let mut output = vec![[0.0f64; 6]; 1024];
+    soxr.process(Some([&input; 6].map(|buf| buf.as_slice())), &mut output).unwrap();
+    assert!(soxr.into_inner().engine().is_ok());
+}
+
+#[test]
+fn test_audio_buffer() {
+    let planar = AudioBuffer::from_planar(&[&[1, 2, 3], &[4, 5, 6]]).unwrap();
+    assert_eq!(planar.layout(), Layout::Planar);
+    assert_eq!(planar.channels(), 2);
+    assert_eq!(planar.frames(), 3);
+    assert_eq!(planar.channel(1).unwrap().copied().collect::<Vec<_>>(), [4, 5, 6]);
+    assert_eq!(planar.frame(2).unwrap().copied().collect::<Vec<_>>(), [3, 6]);
+    assert!(planar.channel(2).is_none());
+    assert!(planar.frame(3).is_none());
+
+    let mut packed = planar.to_layout(Layout::Packed);
+    assert_eq!(packed.as_packed().unwrap(), [1, 4, 2, 5, 3, 6]);
+    packed.frame_mut(0).unwrap().for_each(|sample| *sample = 0);
+    assert_eq!(packed.channel(0).unwrap().copied().collect::<Vec<_>>(), [0, 2, 3]);
+    assert_eq!(packed.to_layout(Layout::Planar).channel(1).unwrap().copied().collect::<Vec<_>>(), [0, 5, 6]);
+
+    assert!(AudioBuffer::from_packed(2, vec![0; 3]).is_err());
+    assert!(AudioBuffer::<i16>::from_planar(&[&[0; 3], &[0; 2]]).is_err());
+
+    let mut soxr = Soxr::<Planar<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, None, None).unwrap();
+    let input = AudioBuffer::with_frames(Layout::Planar, 2, 44100);
+    let mut output = AudioBuffer::new(Layout::Packed, 2);
+
+    let (idone, _) = soxr.process_audio(Some(&input), &mut output).unwrap();
+    assert_eq!(idone, 44100);
+    soxr.process_audio(None, &mut output).unwrap();
+    assert_eq!(output.frames(), 48000);
+
+    let mut output = AudioBuffer::new(Layout::Planar, 2);
+    let err = soxr.process_audio(Some(&input), &mut output).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::LayoutMismatch(DataType::Float32I));
+}
+
+#[test]
+fn test_set_num_channels() {
+    let mut soxr = Soxr::<Planar<f32>, Planar<f32>>::new(44100.0, 48000.0, 2, None, None).unwrap();
+
+    let stereo: &[&[f32]] = &[&vec![0.0; 1024], &vec![0.0; 1024]];
+    let surround: Vec<&[f32]> = vec![stereo[0]; 6];
+    let mut output = vec![vec![0.0; 2048]; 6];
+
+    let mut bufs: Vec<&mut [f32]> = output.iter_mut().take(2).map(|buf| buf.as_mut_slice()).collect();
+    soxr.process(Some(SampleBuffer::Planar(stereo)), SampleBufferMut::Planar(&mut bufs)).unwrap();
+
+    // Switching channels mid-stream, after processing has started
+    soxr.set_num_channels(6).unwrap();
+
+    let mut bufs: Vec<&mut [f32]> = output.iter_mut().map(|buf| buf.as_mut_slice()).collect();
+    let err = soxr.process(Some(SampleBuffer::Planar(stereo)), SampleBufferMut::Planar(&mut bufs)).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::InvalidChannels(2));
+    assert_eq!(err.context().unwrap().channels, 6);
+
+    let mut bufs: Vec<&mut [f32]> = output.iter_mut().map(|buf| buf.as_mut_slice()).collect();
+    let (idone, _) = soxr.process(Some(SampleBuffer::Planar(&surround)), SampleBufferMut::Planar(&mut bufs)).unwrap();
+    assert_eq!(idone, 1024);
+
+    let err = soxr.set_num_channels(0).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::InvalidChannels(0));
+    assert!(soxr.set_num_channels(6).is_ok());
+}
+
+#[test]
+fn test_set_num_channels_pull() {
+    let mut soxr = Soxr::<Planar<f32>, Planar<f32>>::new(44100.0, 48000.0, 2, None, None).unwrap();
+
+    let channels = Arc::new(AtomicUsize::new(0));
+    let source_channels = channels.clone();
+    soxr.set_input_fn(
+        move |buffer: SampleBufferMut<Planar<f32>>| {
+            let SampleBufferMut::Planar(bufs) = buffer else {
+                unreachable!();
+            };
+            source_channels.store(bufs.len(), Ordering::SeqCst);
+            Ok(bufs[0].len())
+        },
+        256,
+    )
+    .unwrap();
+
+    let mut output = vec![vec![0.0; 256]; 6];
+
+    let mut bufs: Vec<&mut [f32]> = output.iter_mut().take(2).map(|buf| buf.as_mut_slice()).collect();
+    soxr.output(SampleBufferMut::Planar(&mut bufs)).unwrap();
+    assert_eq!(channels.load(Ordering::SeqCst), 2);
+
+    soxr.set_num_channels(6).unwrap();
+
+    let mut bufs: Vec<&mut [f32]> = output.iter_mut().map(|buf| buf.as_mut_slice()).collect();
+    soxr.output(SampleBufferMut::Planar(&mut bufs)).unwrap();
+    assert_eq!(channels.load(Ordering::SeqCst), 6);
+}
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+#[test]
+fn test_send_sync() {
+    assert_send::<Soxr<Packed<f32>, Packed<f32>>>();
+    assert_sync::<Soxr<Packed<f32>, Packed<f32>>>();
+    assert_send::<Soxr<Planar<i16>, Planar<f64>>>();
+    assert_sync::<Soxr<Planar<i16>, Planar<f64>>>();
+    assert_send::<Soxr>();
+    assert_send::<Error>();
+    assert_sync::<Error>();
+
+    let soxr = Soxr::<Packed<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, None, None).unwrap();
+
+    let soxr = std::thread::spawn(move || {
+        let mut soxr = soxr;
+        let input: Vec<f32> = vec![0.0; 1024 * 2];
+        let mut output: Vec<f32> = vec![0.0; 1024 * 2];
+
+        soxr.process(Some(SampleBuffer::Packed(&input)), SampleBufferMut::Packed(&mut output)).unwrap();
+        soxr
+    })
+    .join()
+    .unwrap();
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                assert!(soxr.delay() >= 0.0);
+                assert!(soxr.engine().is_ok());
+            });
+        }
+    });
+}
+
+#[test]
+#[cfg_attr(any(feature = "soxr_system", feature = "soxr_runtime"), ignore = "engines depend on how the external libsoxr was built")]
+fn test_engine() {
+    let engine = |recipe, flags| {
+        let quality_spec = QualitySpec::new(recipe, flags).unwrap();
+        Soxr::<Packed<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, Some(&quality_spec), None).unwrap().engine().unwrap()
+    };
+
+    let simd32 = cfg!(feature = "soxr_simd") && cfg!(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"));
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    let simd64 = cfg!(feature = "soxr_avx") && std::arch::is_x86_feature_detected!("avx");
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    let simd64 = false;
+
+    let (engine32, engine64) = match (simd32, simd64) {
+        (true, true) => (Engine::Cr32s, Engine::Cr64s),
+        (true, false) => (Engine::Cr32s, Engine::Cr64),
+        (false, true) => (Engine::Cr32, Engine::Cr64s),
+        (false, false) => (Engine::Cr32, Engine::Cr64),
+    };
+
+    assert_eq!(engine(QualityRecipe::High, QualityFlags::empty()), engine32);
+    assert_eq!(engine(QualityRecipe::High, QualityFlags::DoublePrecision), engine64);
+    assert_eq!(engine(QualityRecipe::VeryHigh, QualityFlags::empty()), engine64);
+    assert_eq!(engine(QualityRecipe::High, QualityFlags::VR), Engine::Vr32);
+}
+
+#[test]
+fn test_engine_properties() {
+    assert_eq!("cr64s".parse::<Engine>().unwrap(), Engine::Cr64s);
+    assert_eq!(Engine::Cr64s.to_string(), "cr64s");
+    assert!(matches!("cr16".parse::<Engine>().err().unwrap().kind(), ErrorKind::UnknownEngine(_)));
+
+    assert!(Engine::Cr64s.is_double_precision() && Engine::Cr64s.is_simd() && !Engine::Cr64s.is_variable_rate());
+    assert!(!Engine::Cr32.is_double_precision() && !Engine::Cr32.is_simd());
+    assert!(Engine::Vr32.is_variable_rate() && !Engine::Vr32.is_double_precision());
+
+    // libsoxr has no double-precision variable-rate engine
+    let quality_spec = QualitySpec::new(QualityRecipe::High, QualityFlags::VR | QualityFlags::DoublePrecision).unwrap();
+    let err = Soxr::<Packed<f32>, Packed<f32>>::new(44100.0, 48000.0, 2, Some(&quality_spec), None).err().unwrap();
+    assert!(matches!(err.kind(), ErrorKind::UnexpectedEngine { .. }));
+}